@@ -7,6 +7,7 @@ use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
 
 use crate::fnutils::*;
 use crate::{db::{create_connection_pool, DBConnection}};
+use crate::handlers::product_handler::{product_pipeline, ProductPipeline};
 use crate::fnutils::ErrorType::EntityNotFound;
 use crate::utils::secutils::verify_token;
 
@@ -18,7 +19,8 @@ pub struct AppDBConnectionPool{
 
 #[derive(Clone)]
 pub struct AppState{
-    pub connection_pool: AppDBConnectionPool
+    pub connection_pool: AppDBConnectionPool,
+    pub product_pipeline: ProductPipeline
 }
 
 
@@ -78,27 +80,22 @@ impl From<FnError<ErrorType>> for AppErrorResponse<ErrorType>{
 }
 
 impl AppState {
-    pub async fn init_connection(&mut self){
-        let connection_pool_result = create_connection_pool().await;
-        if connection_pool_result.is_err() {
-            panic!("Failed to start app");
-        }else {            
-            self.connection_pool = AppDBConnectionPool {
-                connection_pool:Some(connection_pool_result.ok().unwrap())
-            };
+    /// State of the app using the pool, the product pipeline is built once here and shared by the requests
+    pub fn new(connection_pool: Pool<AsyncPgConnection>) -> AppState {
+        AppState {
+            product_pipeline: product_pipeline(connection_pool.clone()),
+            connection_pool: AppDBConnectionPool {
+                connection_pool: Some(connection_pool)
+            },
         }
     }
 }
 
 pub async fn create_app_state() ->AppState{
-    let mut app_state:AppState = AppState{
-        connection_pool: AppDBConnectionPool{
-            connection_pool: None
-        }
-    };
-    app_state.init_connection().await;
-    println!("appstate connection pool {}", app_state.connection_pool.connection_pool.is_none());
-    app_state
+    match create_connection_pool().await {
+        Ok(connection_pool) => AppState::new(connection_pool),
+        Err(_) => panic!("Failed to start app"),
+    }
 }
 
 pub struct DBConnectionHolder {
@@ -26,19 +26,11 @@ impl DBConnection{
     }
     async fn get_connection(&mut self) -> Result<&mut AsyncPgConnection, FnError<ErrorType>>{
         if self.connection.is_none() {
-            let result = self.c_pool.get().await;
-            match result {
-                Ok(conn) => {                    
-                    self.connection = Some(conn);
-                    let  a = &mut self.connection;
-                    let con_ref = a.as_deref_mut().unwrap();
-                    Ok(con_ref)
-                }
-                Err(error) => {                    
-                    let from = ErrorTypeInfo::new((ErrorType::DBError(error.to_string()), error.to_string())).into();
-                    Err(from)
-                }
-            }
+            let conn = pooled_connection(&self.c_pool).await?;
+            self.connection = Some(conn);
+            let  a = &mut self.connection;
+            let con_ref = a.as_deref_mut().unwrap();
+            Ok(con_ref)
         } else {
             let c =self.connection.as_deref_mut().unwrap();
             Ok(c)
@@ -54,6 +46,11 @@ impl DBConnProvider for DBConnection {
     }
 }
 
+/// Takes a connection from the pool, it is returned to the pool when dropped
+pub async fn pooled_connection(pool: &Pool<AsyncPgConnection>) -> Result<Object<AsyncPgConnection>, FnError<ErrorType>> {
+    pool.get().await.map_err(|error| ErrorTypeInfo::new((ErrorType::DBError(error.to_string()), error.to_string())).into())
+}
+
 pub async fn create_connection_pool() -> Result<Pool<AsyncPgConnection>, FnError<ErrorType>> {
     dotenv().ok();
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");    
@@ -270,6 +270,26 @@ impl Into<FnError<ErrorType>> for ErrorTypeInfo{
     }
 }
 
+impl From<function_compose::TimedOut> for FnError<ErrorType>{
+    fn from(timed_out: function_compose::TimedOut) -> Self {
+        FnError {
+            underlying_error: Some(ErrorType::DBError(timed_out.to_string())),
+            description: Some(timed_out.to_string()),
+            error_code: None,
+        }
+    }
+}
+
+impl From<function_compose::CircuitOpen> for FnError<ErrorType>{
+    fn from(open: function_compose::CircuitOpen) -> Self {
+        FnError {
+            underlying_error: Some(ErrorType::DBInitError),
+            description: Some(open.to_string()),
+            error_code: None,
+        }
+    }
+}
+
 impl From<diesel::result::Error> for ErrorType{
     fn from(error: diesel::result::Error) -> Self {
         match &error{
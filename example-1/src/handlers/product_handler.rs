@@ -1,20 +1,34 @@
-use futures::{future::BoxFuture, FutureExt};
+use diesel_async::AsyncPgConnection;
+use diesel_async::pooled_connection::deadpool::Pool;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use function_compose::composeable;
+use function_compose::{compose_shared, composeable, SharedAsyncFn1};
 
-use crate::{db::{DBConnProvider, DBConnection}, repository::{product_repository::{ProductCategoryData, ProductData, ProductRepository}, repository::RepositoryDB}};
+use crate::{db::{pooled_connection, DBConnProvider, DBConnection}, repository::{product_repository::{ProductCategoryData, ProductData, ProductRepository}, repository::RepositoryDB}};
 use crate::fnutils::{ErrorType, FnError};
 
+/// Pipeline finding products by id and packing them for the response, built once and shared by the requests
+pub type ProductPipeline = SharedAsyncFn1<'static, Vec<Uuid>, ProductListDTO, FnError<ErrorType>>;
+
+pub fn product_pipeline(connection_pool: Pool<AsyncPgConnection>) -> ProductPipeline {
+    compose_shared!(find_product_by_ids_in_pool.provide(connection_pool) -> pack_product_data)
+}
+
+
+#[composeable()]
+pub async fn find_product_by_ids(ids:Vec<Uuid>, db_conn: &mut DBConnection) ->Result<Vec<ProductData>, FnError<ErrorType>>{
+    let current_connection = db_conn.current_connection().await?;
+    let mut product_repository = RepositoryDB::from(current_connection);
+    let result = product_repository.get_products_by_ids(ids).await?;
+    Ok(result)
+}
 
 #[composeable()]
-pub fn find_product_by_ids(ids:Vec<Uuid>, db_conn: &mut DBConnection) ->BoxFuture<Result<Vec<ProductData>, FnError<ErrorType>>>{
-    async{
-        let current_connection = db_conn.current_connection().await?;
-        let mut product_repository = RepositoryDB::from(current_connection);
-        let result = product_repository.get_products_by_ids(ids).await?;
-        Ok(result)
-    }.boxed()
+pub async fn find_product_by_ids_in_pool(ids:Vec<Uuid>, connection_pool: Pool<AsyncPgConnection>) ->Result<Vec<ProductData>, FnError<ErrorType>>{
+    let mut connection = pooled_connection(&connection_pool).await?;
+    let mut product_repository = RepositoryDB::from(&mut connection);
+    let result = product_repository.get_products_by_ids(ids).await?;
+    Ok(result)
 }
 
 
@@ -5,6 +5,7 @@ use axum_macros::debug_handler;
 
 use diesel_async::AsyncPgConnection;
 use function_compose::*;
+use function_compose::retry::Fixed;
 use futures::{FutureExt, future::BoxFuture};
 use serde::{Serialize, Deserialize};
 
@@ -15,15 +16,12 @@ use function_compose::composeable;
 use crate::fnutils::{ErrorObject, ErrorType, FnError, map_to_error_object, map_to_unknown_system_time_error, map_to_unknown_var_error};
 
 
-#[composeable()]
+#[composeable(timeout_ms = 5000, circuit_breaker = "user_db")]
 pub  fn authenticate(_auth_request: AuthRequest, _conn: &mut DBConnection) ->BoxFuture<Result<AuthData , FnError<ErrorType>>>{
     async{
         let value: &mut AsyncPgConnection = _conn.current_connection().await?;
         let mut user_repository = RepositoryDB::from(value);
-        let auth_data:AuthData = user_repository
-                .auth(String::from(_auth_request.user), String::from(_auth_request.pass)).await?;
-        
-        Ok(auth_data)
+        compose!(user_repository.auth.provide(_auth_request.pass) -> with_args(_auth_request.user)).await
     }.boxed()
 }
 
@@ -58,18 +56,24 @@ pub struct AuthResponse{
 }
 
 #[composeable()]
-pub fn  pack_auth_result(user_data: (User, String)) -> Result<AuthResponse, FnError<ErrorType>>{
+pub fn  pack_auth_result(user: User, token: String) -> Result<AuthResponse, FnError<ErrorType>>{
     Ok(AuthResponse{
-        token: user_data.1,
-        email_verified: user_data.0.isemail_verfied.is_some_and(|e|e),
-        email: user_data.0.email,
-        mobile: user_data.0.phone_number.unwrap_or_default(),
-        mobile_verified_status: user_data.0.is_phone_verfied.is_some_and(|e|e),
-        is_active: user_data.0.is_active.is_some_and(|e|e),
+        token,
+        email_verified: user.isemail_verfied.is_some_and(|e|e),
+        email: user.email,
+        mobile: user.phone_number.unwrap_or_default(),
+        mobile_verified_status: user.is_phone_verfied.is_some_and(|e|e),
+        is_active: user.is_active.is_some_and(|e|e),
         location_identified:false,
     })
 }
 
+pipeline! {
+    pub async fn auth_flow(auth_request: AuthRequest, conn: &mut DBConnection) -> Result<AuthResponse, FnError<ErrorType>> {
+        authenticate -> generate_token ->> pack_auth_result
+    }
+}
+
 
 impl From<CreateUserRequest> for NewUser{
     fn from(create_user_request: CreateUserRequest) -> Self {
@@ -85,12 +89,16 @@ impl From<CreateUserRequest> for NewUser{
 
 #[debug_handler(state=AppState)]
 pub async  fn create_mobile_user_handler(mut db_conn1: DBConnectionHolder, Json(payload): Json<CreateUserRequest>) ->Result<Json<User>, ErrorObject>{
-    let user = compose!(create_mobile_user.provide(&mut db_conn1) -> with_args(payload)).await.map_err(map_to_error_object())?;
+    let user = compose!(create_mobile_user.provide(&mut db_conn1) -> map_err(map_to_error_object()) -> with_args(payload)).await?;
     //.await?;
     Ok(Json(user))
 }
 
-#[composeable()]
+fn is_transient_db_error(error: &FnError<ErrorType>) -> bool {
+    matches!(error.underlying_error, Some(ErrorType::DBError(_)))
+}
+
+#[composeable(retry = Fixed::from_millis(100).take(2), retry_if = is_transient_db_error)]
 pub fn create_mobile_user(create_user_request:CreateUserRequest,_conn: &mut DBConnection)->BoxFuture<Result<User, FnError<ErrorType>>>{
     async{
         let value: &mut AsyncPgConnection = _conn.current_connection().await?;
@@ -114,7 +122,7 @@ pub struct AuthRequest{
 }
 
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct CreateUserRequest{
     name:String,
     first_name:String,
@@ -6,7 +6,7 @@ use futures::future::BoxFuture;
 use futures::FutureExt;
 use tower_http::cors::CorsLayer;
 
-use example::axumutils::{AppDBConnectionPool, AppState, DBConnectionHolder};
+use example::axumutils::{create_app_state, AppState, DBConnectionHolder};
 use example::db::{DBConnection, DBConnProvider};
 use example::fnutils::{ErrorObject, ErrorType, FnError, map_to_error_object};
 use example::handlers::user::*;
@@ -16,19 +16,8 @@ use example::repository::user_repository::{NewUser, UserRepository};
 use example::routes::product_route::get_product_by_ids;
 use function_compose::{compose, composeable};
 
-pub async fn create_app_state() ->AppState{
-    let mut app_state:AppState = AppState{
-        connection_pool: AppDBConnectionPool{
-            connection_pool: None
-        }
-    };
-    app_state.init_connection().await;
-    println!("appstate connection pool {}", app_state.connection_pool.connection_pool.is_none());
-    app_state
-}
-
 pub async  fn create_mobile_user_handler(mut db_conn1: DBConnectionHolder, Json(payload): Json<CreateUserRequest>) ->Result<Json<User>, ErrorObject>{
-    let user = compose!(create_mobile_user.provide(&mut db_conn1) -> with_args(payload)).await.map_err(map_to_error_object())?;
+    let user = compose!(create_mobile_user.provide(&mut db_conn1) -> map_err(map_to_error_object()) -> with_args(payload)).await?;
     //.await?;
     Ok(Json(user))
 }
@@ -37,11 +26,7 @@ pub async  fn create_mobile_user_handler(mut db_conn1: DBConnectionHolder, Json(
 
 #[debug_handler(state=AppState)]
 pub async fn user_auth_handler(mut db_conn1: DBConnectionHolder, Json(auth_request): Json<AuthRequest>) -> Result<Json<AuthResponse>, ErrorObject>{
-        let r:AuthResponse = compose!(
-            authenticate.provide(&mut db_conn1) -> 
-            generate_token -> 
-            pack_auth_result -> 
-            with_args(auth_request)).await?;                
+        let r:AuthResponse = compose!(auth_flow.provide(&mut db_conn1) -> with_args(auth_request)).await?;
         Ok(Json(r))
 }
 
@@ -21,6 +21,7 @@ use diesel::JoinOnDsl;
 
 use serde::Deserialize;
 use uuid::Uuid;
+use function_compose::composeable;
 
 use crate::model::*;
 
@@ -43,6 +44,7 @@ sql_function!(fn lower(x: Text) -> Text);
 
 #[async_trait]
 pub trait UserRepository/* :CrudRepository<User,i64> */{
+     #[composeable()]
      async fn auth(&mut self, user_name: String, pass: String) ->Result<AuthData, FnError<ErrorType>>;
 
      async fn create_mobile_user(&mut self, user:NewUser)->Result<User, FnError<ErrorType>>;
@@ -1,7 +1,8 @@
 use function_compose::composeable;
 use function_compose::compose;
+use function_compose::compose_shared;
 use futures::{FutureExt, future::BoxFuture};
-use retry::delay::Fixed;
+use function_compose::retry::Fixed;
 
 #[composeable]
 fn add_10(a: u32) -> Result<u32, String>{
@@ -92,4 +93,8 @@ async fn main() {
 
     let result = compose!(add_3_arg_ref_async.provide(one).provide(one) -> add_10 -> with_args(&10)).await;    
     assert_eq!(22, result.unwrap());
+
+    let add_pipeline = compose_shared!(add_10 -> add_async.provide(10));
+    assert_eq!(30, add_pipeline(10).await.unwrap());
+    assert_eq!(40, add_pipeline(20).await.unwrap());
 }
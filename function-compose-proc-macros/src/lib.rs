@@ -5,11 +5,11 @@
 
 use proc_macro::TokenStream;
 
-use std::fmt::Formatter;
-use std::{fmt::Display, ops::Deref};
+use std::ops::Deref;
 
 use quote::{quote, ToTokens, TokenStreamExt};
 use syn::parse::ParseStream;
+use syn::spanned::Spanned;
 use syn::{parse::Parse, Expr, FnArg, ItemFn, ReturnType, Token, Type};
 
 use crate::OptionalRetry::SomeRetry;
@@ -20,6 +20,14 @@ fn generate_return_type_param(index: u8) -> String {
 
 mod keyword {
     syn::custom_keyword!(retry);
+    syn::custom_keyword!(retry_if);
+    syn::custom_keyword!(on_retry);
+    syn::custom_keyword!(report_exhausted);
+    syn::custom_keyword!(timeout_ms);
+    syn::custom_keyword!(deadline_ms);
+    syn::custom_keyword!(circuit_breaker);
+    syn::custom_keyword!(breaker_if);
+    syn::custom_keyword!(inject);
 }
 
 fn generate_generics_parameters(count: u8) -> String {
@@ -31,12 +39,65 @@ fn generate_generics_parameters(count: u8) -> String {
 }
 
 #[proc_macro_attribute]
-pub fn retry(_attr: TokenStream, _item: TokenStream) -> TokenStream {
-    panic!()
+pub fn retry(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = proc_macro2::TokenStream::from(attr);
+    let error = syn::Error::new(
+        proc_macro2::Span::call_site(),
+        format!("retry is an argument of composeable, use `#[composeable(retry = {attr})]`"),
+    );
+    with_item(error, item)
+}
+
+/// Compile error followed by the unchanged item, so that uses of the item do not report further errors
+fn with_item(error: syn::Error, item: TokenStream) -> TokenStream {
+    let mut tokens = error.to_compile_error();
+    tokens.extend(proc_macro2::TokenStream::from(item));
+    tokens.into()
 }
 
 struct Retry {
     strategy: Expr,
+    /// predicate(`Fn(&E) -> bool`) deciding if an error is retried, all errors are retried if None
+    condition: Option<Expr>,
+    /// hook(`Fn(usize, Duration, &E)`) called with the attempt number, the delay and the error before every retry
+    on_retry: Option<Expr>,
+    /// converts RetryExhausted to the error type of the function when all attempts fail
+    report_exhausted: bool,
+    /// time limit of all attempts including the delays, each attempt is limited by `timeout_ms`
+    deadline: Option<TimeLimit>,
+}
+
+/// Time limit in milliseconds given as an argument of the attribute, e.g `timeout_ms = 500`
+struct TimeLimit {
+    name: syn::Ident,
+    millis: u64,
+}
+
+impl TimeLimit {
+    fn duration(&self) -> proc_macro2::TokenStream {
+        let millis = self.millis;
+        quote! { ::std::time::Duration::from_millis(#millis) }
+    }
+
+    /// Wraps the future returning the Result of the function, the elapsed limit is converted into its error type
+    fn limit(limit: Option<&TimeLimit>, future: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match limit {
+            Some(limit) => {
+                let duration = limit.duration();
+                quote! { ::function_compose::with_timeout(#duration, #future) }
+            }
+            None => future,
+        }
+    }
+}
+
+impl Parse for TimeLimit {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: syn::Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let millis = input.parse::<syn::LitInt>()?.base10_parse::<u64>()?;
+        Ok(TimeLimit { name, millis })
+    }
 }
 
 struct FunctionArgs<'a> {
@@ -68,15 +129,14 @@ impl<'a> ToTokens for FunctionArgs<'a> {
                             }
                         }
                         _ => {
+                            let cloned = clone_for_retry(ident, ty);
                             quote! {
-                                 #[allow(unused)] #ident,
+                                 #[allow(unused)] #cloned,
                             }
                         }
                     };
 
                     tokens.append_all(token_stream.into_iter());
-                    /*let mut toeknStream: proc_macro::TokenStream = tokeStream.into();
-                    toeknStream.extend(tokens.into_iter())*/
                 }
             }
         });
@@ -99,82 +159,386 @@ enum OptionalRetry {
     NoRetry,
 }
 
-impl Display for Retry {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", "test")
-    }
-}
-
 impl Parse for OptionalRetry {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let lookahead = input.lookahead1();
         if lookahead.peek(keyword::retry) {
-            let _ = input.parse::<keyword::retry>();
-            let _ = input.parse::<Token![=]>();
+            let keyword = input.parse::<keyword::retry>()?;
+            if !input.peek(Token![=]) {
+                return Err(syn::Error::new_spanned(keyword, "expected `retry = <delay strategy>`, e.g `retry = Fixed::from_millis(100).take(3)`"));
+            }
+            input.parse::<Token![=]>()?;
             let expr: Expr = input.parse()?;
-            println!("{}", expr.to_token_stream());
-            Ok(OptionalRetry::SomeRetry(Retry { strategy: expr }))
+            Ok(OptionalRetry::SomeRetry(Retry { strategy: expr, condition: None, on_retry: None, report_exhausted: false, deadline: None }))
         } else {
             Ok(OptionalRetry::NoRetry)
         }
     }
 }
 
+/// Options of the retry strategy given as separate arguments of the attribute, e.g `retry_if = is_transient`
+#[derive(Default)]
+struct RetryOptions {
+    condition: Option<Expr>,
+    on_retry: Option<Expr>,
+    report_exhausted: bool,
+    deadline: Option<TimeLimit>,
+    /// name of the first option, reported if no retry strategy is given
+    first: Option<syn::Ident>,
+}
+
+/// Arguments of the composeable attribute, e.g `#[composeable(retry = Fixed::from_millis(100), retry_if = is_transient, inject(conn))]`
+struct ComposeableArgs {
+    retry: OptionalRetry,
+    inject: Vec<syn::Ident>,
+    /// time limit of every call, or of every attempt of a retried function
+    timeout: Option<TimeLimit>,
+    /// name of the circuit breaker guarding every call, including all its retries
+    circuit_breaker: Option<syn::LitStr>,
+    /// errors counted as failures by the circuit breaker, by default every error which exhausted the retries
+    breaker_if: Option<Expr>,
+}
+
+impl Parse for ComposeableArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut retry = OptionalRetry::NoRetry;
+        let mut options = RetryOptions::default();
+        let mut inject = Vec::new();
+        let mut timeout: Option<TimeLimit> = None;
+        let mut circuit_breaker: Option<syn::LitStr> = None;
+        let mut breaker_if: Option<(syn::Ident, Expr)> = None;
+        while !input.is_empty() {
+            let lookahead = input.lookahead1();
+            if lookahead.peek(keyword::inject) {
+                let keyword = input.parse::<keyword::inject>()?;
+                if !inject.is_empty() {
+                    return Err(syn::Error::new_spanned(keyword, "duplicate `inject` argument"));
+                }
+                let content;
+                syn::parenthesized!(content in input);
+                inject.extend(content.parse_terminated(syn::Ident::parse, Token![,])?);
+                if inject.is_empty() {
+                    return Err(syn::Error::new_spanned(keyword, "`inject` requires at least one argument name"));
+                }
+            } else if lookahead.peek(keyword::retry) {
+                if let SomeRetry(_) = retry {
+                    return Err(syn::Error::new(input.span(), "duplicate `retry` argument"));
+                }
+                retry = input.parse()?;
+            } else if lookahead.peek(keyword::retry_if) || lookahead.peek(keyword::on_retry) {
+                let name: syn::Ident = input.parse()?;
+                let option = if name == "retry_if" { &mut options.condition } else { &mut options.on_retry };
+                if option.is_some() {
+                    return Err(syn::Error::new_spanned(&name, format!("duplicate `{name}` argument")));
+                }
+                input.parse::<Token![=]>()?;
+                *option = Some(input.parse()?);
+                options.first.get_or_insert(name);
+            } else if lookahead.peek(keyword::report_exhausted) {
+                let name: syn::Ident = input.parse()?;
+                if options.report_exhausted {
+                    return Err(syn::Error::new_spanned(&name, "duplicate `report_exhausted` argument"));
+                }
+                options.report_exhausted = true;
+                options.first.get_or_insert(name);
+            } else if lookahead.peek(keyword::timeout_ms) || lookahead.peek(keyword::deadline_ms) {
+                let limit: TimeLimit = input.parse()?;
+                let option = if limit.name == "timeout_ms" { &mut timeout } else { &mut options.deadline };
+                if option.is_some() {
+                    return Err(syn::Error::new_spanned(&limit.name, format!("duplicate `{}` argument", limit.name)));
+                }
+                if limit.name == "deadline_ms" {
+                    options.first.get_or_insert(limit.name.clone());
+                }
+                *option = Some(limit);
+            } else if lookahead.peek(keyword::circuit_breaker) {
+                let keyword = input.parse::<keyword::circuit_breaker>()?;
+                if circuit_breaker.is_some() {
+                    return Err(syn::Error::new_spanned(keyword, "duplicate `circuit_breaker` argument"));
+                }
+                input.parse::<Token![=]>()?;
+                let name: syn::LitStr = input.parse()?;
+                if name.value().is_empty() {
+                    return Err(syn::Error::new_spanned(name, "circuit breaker name must not be empty"));
+                }
+                circuit_breaker = Some(name);
+            } else if lookahead.peek(keyword::breaker_if) {
+                let name: syn::Ident = input.parse()?;
+                if breaker_if.is_some() {
+                    return Err(syn::Error::new_spanned(&name, "duplicate `breaker_if` argument"));
+                }
+                input.parse::<Token![=]>()?;
+                breaker_if = Some((name, input.parse()?));
+            } else {
+                return Err(lookahead.error());
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        match (&mut retry, options.first) {
+            (SomeRetry(retry), _) => {
+                retry.condition = options.condition;
+                retry.on_retry = options.on_retry;
+                retry.report_exhausted = options.report_exhausted;
+                retry.deadline = options.deadline;
+            }
+            (OptionalRetry::NoRetry, Some(name)) => {
+                return Err(syn::Error::new_spanned(
+                    &name,
+                    format!("`{name}` requires a retry strategy, add e.g `retry = Fixed::from_millis(100).take(3)`"),
+                ));
+            }
+            _ => {}
+        }
+        if let (Some((name, _)), None) = (&breaker_if, &circuit_breaker) {
+            return Err(syn::Error::new_spanned(
+                name,
+                "`breaker_if` requires a circuit breaker, add e.g `circuit_breaker = \"name\"`",
+            ));
+        }
+        let breaker_if = breaker_if.map(|(_, condition)| condition);
+        Ok(ComposeableArgs { retry, inject, timeout, circuit_breaker, breaker_if })
+    }
+}
 
 fn generate_ident_with_prefix(ident: &str) -> String{
     format!("fn_composer__{}", ident)
 }
 
+/// Describes how a composeable function produces its result
+enum AsyncKind {
+    /// function returns Result directly
+    Sync,
+    /// function returns BoxFuture (or the equivalent Pin<Box<dyn Future + Send>>) of Result
+    BoxedFuture,
+    /// async fn or function returning impl Future. Holds the Output type of the future
+    NativeFuture(Type),
+}
+
+fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last(),
+        _ => None,
+    }
+}
+
+fn first_type_arg(segment: &syn::PathSegment) -> Option<&Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn future_output(bounds: &syn::punctuated::Punctuated<syn::TypeParamBound, Token![+]>) -> Option<Type> {
+    bounds.iter().find_map(|bound| match bound {
+        syn::TypeParamBound::Trait(trait_bound) => {
+            let segment = trait_bound.path.segments.last()?;
+            if segment.ident != "Future" {
+                return None;
+            }
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::AssocType(assoc) if assoc.ident == "Output" => Some(assoc.ty.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            }
+        }
+        _ => None,
+    })
+}
+
+fn is_boxed_future(ty: &Type) -> bool {
+    match last_segment(ty) {
+        Some(segment) if segment.ident == "BoxFuture" => true,
+        Some(segment) if segment.ident == "Pin" => first_type_arg(segment)
+            .and_then(last_segment)
+            .filter(|boxed| boxed.ident == "Box")
+            .and_then(first_type_arg)
+            .map(|inner| matches!(inner, Type::TraitObject(trait_object) if future_output(&trait_object.bounds).is_some()))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Output type of BoxFuture<'a, Output> or Pin<Box<dyn Future<Output = Output>>>
+fn boxed_future_output(ty: &Type) -> Option<Type> {
+    let segment = last_segment(ty)?;
+    if segment.ident == "BoxFuture" {
+        return first_type_arg(segment).cloned();
+    }
+    match first_type_arg(segment).and_then(last_segment).and_then(first_type_arg)? {
+        Type::TraitObject(trait_object) => future_output(&trait_object.bounds),
+        _ => None,
+    }
+}
+
+fn async_kind(sig: &syn::Signature) -> AsyncKind {
+    let return_type = match &sig.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some(ty.deref()),
+    };
+    if sig.asyncness.is_some() {
+        let output = return_type.cloned().unwrap_or_else(|| syn::parse_quote!(()));
+        return AsyncKind::NativeFuture(output);
+    }
+    match return_type {
+        Some(ty) if is_boxed_future(ty) => AsyncKind::BoxedFuture,
+        Some(Type::ImplTrait(impl_trait)) => match future_output(&impl_trait.bounds) {
+            Some(output) => AsyncKind::NativeFuture(output),
+            None => AsyncKind::Sync,
+        },
+        _ => AsyncKind::Sync,
+    }
+}
+
+
+/// Replaces the receiver of a method with the typed argument `fn_composer_self`(`&Self`, `&mut Self` or `Self`),
+/// so that the helpers of a method are generated the same way as for a function taking the receiver as first argument.
+/// Arguments declared `mut` are declared without it, the helpers declare all their arguments `mut`.
+fn receiver_as_typed_arg(sig: &syn::Signature) -> syn::Signature {
+    let mut sig = sig.clone();
+    if let Some(FnArg::Receiver(receiver)) = sig.inputs.first() {
+        let ty = &receiver.ty;
+        sig.inputs[0] = syn::parse_quote! { fn_composer_self: #ty };
+    }
+    for arg in sig.inputs.iter_mut() {
+        if let FnArg::Typed(pat_type) = arg {
+            if let syn::Pat::Ident(pat_ident) = pat_type.pat.as_mut() {
+                pat_ident.mutability = None;
+            }
+        }
+    }
+    sig
+}
+
+/// Rejects signatures the helpers cannot be generated for.
+///
+/// A missing return type is rejected here, whether the return type(or Output of the returned future) is a Result is checked
+/// by the compiler through the `FnResult` bound of the retry function(see composeable_fns), so that aliases of Result are accepted.
+fn validate_signature(sig: &syn::Signature) -> syn::Result<()> {
+    if let Some(unsafety) = &sig.unsafety {
+        return Err(syn::Error::new_spanned(unsafety, "unsafe functions cannot be composeable"));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new_spanned(variadic, "variadic functions cannot be composeable"));
+    }
+    if sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(&sig.ident, "composeable function must take at least one argument"));
+    }
+    if sig.inputs.len() > 16 {
+        return Err(syn::Error::new_spanned(&sig.inputs, "composeable function can take at most 16 arguments"));
+    }
+    for arg in &sig.inputs {
+        match arg {
+            FnArg::Receiver(receiver) if receiver.colon_token.is_some() => {
+                return Err(syn::Error::new_spanned(receiver, "composeable methods must take `self`, `&self` or `&mut self`"));
+            }
+            FnArg::Receiver(_) => {}
+            FnArg::Typed(pat_type) => match pat_type.pat.as_ref() {
+                syn::Pat::Ident(pat_ident) if pat_ident.by_ref.is_none() && pat_ident.subpat.is_none() => {}
+                pat => {
+                    return Err(syn::Error::new_spanned(
+                        pat,
+                        "arguments of a composeable function must be identifiers, destructure the argument in the function body",
+                    ));
+                }
+            },
+        }
+    }
+    let return_type = match &sig.output {
+        ReturnType::Default => {
+            return Err(syn::Error::new_spanned(&sig.ident, "composeable function must return Result, e.g `-> Result<T, E>`"));
+        }
+        ReturnType::Type(_, ty) => ty,
+    };
+    let kind = async_kind(sig);
+    if matches!(kind, AsyncKind::Sync) && matches!(return_type.as_ref(), Type::ImplTrait(_)) {
+        return Err(syn::Error::new_spanned(return_type, "composeable function must return Result or a Future of Result"));
+    }
+    result_type(sig, &kind).map(|_| ())
+}
 
 #[proc_macro_attribute]
 pub fn composeable(attr: TokenStream, item: TokenStream) -> TokenStream {
-    
+    match composeable_fns(attr.into(), item.clone().into()) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => with_item(error, item),
+    }
+}
 
-    let token_stream_clone = item.clone();
-    let item_fn: ItemFn = syn::parse_macro_input!(token_stream_clone);
+/// Generates the helpers of a composeable function followed by the function itself
+fn composeable_fns(attr: proc_macro2::TokenStream, item: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let (item_vis, declared_sig) = match syn::parse2::<ItemFn>(item.clone()) {
+        Ok(item_fn) => (item_fn.vis, item_fn.sig),
+        //method declared in a trait without a default body
+        Err(error) => match syn::parse2::<syn::TraitItemFn>(item.clone()) {
+            Ok(trait_fn) => (syn::Visibility::Inherited, trait_fn.sig),
+            Err(_) => return Err(error),
+        },
+    };
+    let ComposeableArgs { retry, inject, timeout, circuit_breaker, breaker_if } = syn::parse2(attr)?;
+    validate_signature(&declared_sig)?;
+    let receiver = declared_sig.receiver().cloned();
+    let is_method = receiver.is_some();
+    let item_fn_sig = receiver_as_typed_arg(&declared_sig);
 
-    let fn_gen = item_fn.sig.generics;
-    let mut async_fn = item_fn.sig.asyncness.is_some();
-    let input_args = item_fn.sig.inputs;
+    let async_kind = async_kind(&item_fn_sig);
+    let async_fn = !matches!(async_kind, AsyncKind::Sync);
+    let deadline = match &retry {
+        SomeRetry(retry) => retry.deadline.as_ref(),
+        OptionalRetry::NoRetry => None,
+    };
+    if let Some(limit) = timeout.iter().chain(deadline).find(|_| !async_fn) {
+        return Err(syn::Error::new_spanned(
+            &limit.name,
+            format!("`{}` requires an async function, a sync function cannot be interrupted", limit.name),
+        ));
+    }
+    let fn_gen = item_fn_sig.generics.clone();
+    let input_args = item_fn_sig.inputs.clone();
     let arg_tokens: Vec<_> = input_args.iter().collect();
     let mut_arg_tokens: Vec<_> = input_args.iter().collect();
     let arg_length = input_args.len();
-    let fn_ident = &item_fn.sig.ident;
-    let fn_name = item_fn.sig.ident.to_string();
-    let fn_return_type = &item_fn.sig.output;
+    let fn_ident = &item_fn_sig.ident;
+    let fn_name = item_fn_sig.ident.to_string();
+    let fn_return_type = &item_fn_sig.output;
 
     let return_type_without_token = match fn_return_type {
         ReturnType::Default => None,
         ReturnType::Type(_, return_type) => Some(return_type),
     };
 
-    let retry = syn::parse_macro_input!(attr as OptionalRetry);
-
-    if !async_fn {
-        match fn_return_type {
-            syn::ReturnType::Default => {}
-            syn::ReturnType::Type(_, t) => {
-                let x = t.deref();
-                async_fn = x.to_token_stream().to_string().starts_with("BoxFuture");
-            }
-        }
+    //helpers of a function are associated functions of its marker type(see Composeable). Types cannot be declared in an impl,
+    //so helpers of a method are associated functions of the same impl(or trait) prefixed with the name of the method, they call
+    //the method through Self and are not available on trait objects. All helpers have the visibility of the function
+    let turbofish = turbofish(&item_fn_sig);
+    let helper_vis = quote! { #item_vis };
+    let (fn_path, self_bound) = if is_method {
+        (quote! { Self::#fn_ident #turbofish }, quote! { where Self: Sized })
+    } else {
+        (quote! { #fn_ident #turbofish }, quote! {})
+    };
+    let helper_ident = |name: &str| {
+        let name = if is_method { generate_ident_with_prefix(&format!("{name}_{fn_name}")) } else { name.to_owned() };
+        syn::Ident::new(&name, proc_macro2::Span::call_site())
+    };
+    let mut retry_generics = fn_gen.clone();
+    if is_method {
+        retry_generics.make_where_clause().predicates.push(syn::parse_quote! { Self: Sized });
     }
-    let lifted_fn_name = "lifted_fn_".to_owned() + &fn_name;
-    let prefixed_lifted_fn_name = &generate_ident_with_prefix(&lifted_fn_name);
-    //let lift_retry_fn_name = &generate_ident_with_prefix(&("retry_".to_owned() + &lifted_fn_name));
-    let lift_fn_ident = syn::Ident::new(prefixed_lifted_fn_name, proc_macro2::Span::call_site());
-    let is_retry_fn_name = &generate_ident_with_prefix(&("is_retryable_".to_owned() + &fn_name));
-    let is_retry_fn_ident = syn::Ident::new(&is_retry_fn_name, proc_macro2::Span::call_site());
-    let retry_fn_ident = syn::Ident::new(
-        &generate_ident_with_prefix(&("retry_".to_owned() + &fn_name)),
-        proc_macro2::Span::call_site(),
-    );
+    //a return type which is not a Result is reported at the return type by the diagnostic of FnResult
+    let output = result_type(&item_fn_sig, &async_kind)?;
+    retry_generics.make_where_clause().predicates.push(syn::parse_quote_spanned! {output.span()=> #output: ::function_compose::FnResult });
+    let retry_where_clause = &retry_generics.where_clause;
 
-    let async_fn_name = &generate_ident_with_prefix(&("is_async_".to_owned() + fn_name.deref()));
-    let async_fn_ident = syn::Ident::new(
-        async_fn_name,
-        proc_macro2::Span::call_site(),
-    );
+    let lift_fn_ident = helper_ident("lifted_fn");
+    let lift_shared_fn_ident = helper_ident("lifted_shared_fn");
+    let retry_fn_ident = helper_ident("retry");
     let (
         _return_type,
         _underlying_lift_fn_name,
@@ -182,7 +546,9 @@ pub fn composeable(attr: TokenStream, item: TokenStream) -> TokenStream {
         return_type_ident,
         ret_gen,
         underlying_lift_fn_name_ident,
-    ) = /*if (asyncFn)*/ {
+        shared_return_type_ident,
+        underlying_lift_shared_fn_name_ident,
+    ) = {
         let return_type = if async_fn {
             "BoxedAsyncFn".to_owned() + arg_length.to_string().as_str()
         } else {
@@ -196,23 +562,35 @@ pub fn composeable(attr: TokenStream, item: TokenStream) -> TokenStream {
         let gen_type_params = generate_generics_parameters((arg_length + 1) as u8);
         let fun_arg_params = generate_generics_parameters((arg_length) as u8);
         let return_type_param = generate_return_type_param((arg_length + 1) as u8);
-        let fun_gen = if async_fn {
-            let gen_type = format!("<'a, {gen_type_params} E1, F:Fn({fun_arg_params})->BoxFuture<'a,Result<{return_type_param}, E1>> + 'a + Send +Sync>", );            
+        let fun_gen = if let AsyncKind::NativeFuture(_) = async_kind {
+            let gen_type = format!("<'a, {gen_type_params} E1, Fut: ::std::future::Future<Output = Result<{return_type_param}, E1>> + Send + 'a, F:Fn({fun_arg_params})->Fut + 'a + Send +Sync>");
+            syn::parse_str::<syn::Generics>(
+                gen_type.as_str()
+            )?
+        } else if async_fn {
+            let gen_type = format!("<'a, {gen_type_params} E1, F:Fn({fun_arg_params})->::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<{return_type_param}, E1>> + Send + 'a>> + 'a + Send +Sync>", );            
             syn::parse_str::<syn::Generics>(
                 gen_type.as_str()
-            ).ok()
-                .unwrap()
+            )?
         } else {
             let gen_type  =format!("<'a, {gen_type_params} E1, F:Fn({fun_arg_params})->Result<{return_type_param}, E1> + Send +Sync + 'a>");            
             syn::parse_str::<syn::Generics>(
                 gen_type.as_str()                
-            ).ok().unwrap()
+            )?
         };
 
         let return_type_ident = syn::Ident::new(return_type.as_str(), proc_macro2::Span::call_site());
         let underlying_lift_fn_name_ident =
             syn::Ident::new(underlying_lift_fn_name.as_str(), proc_macro2::Span::call_site());
-        let ret_gen = syn::parse_str::<syn::Generics>(format!("<'a,{gen_type_params} E1>").as_str()).ok().unwrap();
+        let shared_return_type_ident = syn::Ident::new(
+            &return_type.replace("Boxed", "Shared"),
+            proc_macro2::Span::call_site(),
+        );
+        let underlying_lift_shared_fn_name_ident = syn::Ident::new(
+            &underlying_lift_fn_name.replace("lift_", "lift_shared_"),
+            proc_macro2::Span::call_site(),
+        );
+        let ret_gen = syn::parse_str::<syn::Generics>(format!("<'a,{gen_type_params} E1>").as_str())?;
 
         (
             return_type,
@@ -221,40 +599,119 @@ pub fn composeable(attr: TokenStream, item: TokenStream) -> TokenStream {
             return_type_ident,
             ret_gen,
             underlying_lift_fn_name_ident,
+            shared_return_type_ident,
+            underlying_lift_shared_fn_name_ident,
         )
     };
 
-    match retry {
-        OptionalRetry::NoRetry => {
-            let function_mut_args = FunctionMutArgs {
-                args: mut_arg_tokens,
-            };
-            let tokens: proc_macro2::TokenStream = quote! {
-                use function_compose::*;
+    //native futures are boxed by the lifted function so that they can be coerced to BoxedAsyncFn/SharedAsyncFn
+    let lifted_f = if let AsyncKind::NativeFuture(_) = async_kind {
+        let closure_args: Vec<_> = (1..=arg_length)
+            .map(|i| syn::Ident::new(&format!("t{i}"), proc_macro2::Span::call_site()))
+            .collect();
+        quote! { move |#( #closure_args ),*| Box::pin(f(#( #closure_args ),*)) }
+    } else {
+        quote! { f }
+    };
+    //return type of the generated retry function. Native futures are retried in an async fn
+    let (retry_asyncness, retry_return_type) = match &async_kind {
+        AsyncKind::NativeFuture(output) => (quote! { async }, quote! { -> #output }),
+        _ => (quote! {}, quote! { #fn_return_type }),
+    };
+    //return type of the function forwarding to non retryable function
+    let forward_return_type = match &async_kind {
+        AsyncKind::NativeFuture(output) => quote! { -> impl ::std::future::Future<Output = #output> },
+        _ => quote! { #fn_return_type },
+    };
+    let lift_step_fn_ident = helper_ident("lifted_step");
+    let (step_gen, step_type) = {
+        let gen_type_params = generate_generics_parameters((arg_length + 1) as u8);
+        let fun_arg_params = generate_generics_parameters(arg_length as u8);
+        let return_type_param = generate_return_type_param((arg_length + 1) as u8);
+        let (step_gen, step_type) = if async_fn {
+            (
+                format!("<{gen_type_params} E1, Fut: ::std::future::Future<Output = Result<{return_type_param}, E1>>, F: FnOnce({fun_arg_params}) -> Fut>"),
+                format!("::function_compose::AsyncStep<F, ({fun_arg_params})>"),
+            )
+        } else {
+            (
+                format!("<{gen_type_params} E1, F: FnOnce({fun_arg_params}) -> Result<{return_type_param}, E1>>"),
+                format!("::function_compose::SyncStep<F, ({fun_arg_params})>"),
+            )
+        };
+        (
+            syn::parse_str::<syn::Generics>(&step_gen)?,
+            syn::parse_str::<Type>(&step_type)?,
+        )
+    };
 
-                pub fn #lift_fn_ident #fun_gen(f: F)  -> #return_type_ident #ret_gen{
-                    #underlying_lift_fn_name_ident(f)
-                }
+    let retry_fn_ident_ref = &retry_fn_ident;
+    let bound_tokens = match &receiver {
+        Some(receiver) if !inject.is_empty() => {
+            return Err(syn::Error::new_spanned(receiver, "methods cannot inject arguments"));
+        }
+        Some(receiver) => {
+            let bound_idents = [&helper_ident("bound"), &helper_ident("bound_step")];
+            bound_method_fns(&item_fn_sig, receiver, &async_kind, &helper_vis, bound_idents, retry_fn_ident_ref)?
+        }
+        None => quote! {},
+    };
+    let lift_tokens = if inject.is_empty() {
+        quote! {
+            #helper_vis fn #lift_fn_ident #fun_gen(f: F)  -> ::function_compose::#return_type_ident #ret_gen #self_bound {
+                ::function_compose::#underlying_lift_fn_name_ident(#lifted_f)
+            }
 
-                pub fn #async_fn_ident ()  -> bool{
-                    #async_fn
-                }
+            #helper_vis fn #lift_shared_fn_ident #fun_gen(f: F)  -> ::function_compose::#shared_return_type_ident #ret_gen #self_bound {
+                ::function_compose::#underlying_lift_shared_fn_name_ident(#lifted_f)
+            }
 
-                 pub fn #is_retry_fn_ident ()  -> bool{
-                         false
-                    }
+            #helper_vis fn #lift_step_fn_ident #step_gen(f: F)  -> #step_type #self_bound {
+                <#step_type>::new(f)
+            }
+        }
+    } else {
+        let lift_idents = [&lift_fn_ident, &lift_shared_fn_ident, &lift_step_fn_ident];
+        injected_lift_fns(&item_fn_sig, &inject, &async_kind, &helper_vis, lift_idents, retry_fn_ident_ref)?
+    };
 
+    let retryable = matches!(retry, SomeRetry(_));
+    let retry_tokens: proc_macro2::TokenStream = match retry {
+        OptionalRetry::NoRetry => {
+            let forwarded_args: Vec<_> = arg_tokens
+                .iter()
+                .filter_map(|arg| match arg {
+                    FnArg::Receiver(_) => None,
+                    FnArg::Typed(pat_type) => Some(&pat_type.pat),
+                })
+                .collect();
+            let function_mut_args = FunctionMutArgs {
+                args: mut_arg_tokens,
+            };
+            let forward_call = TimeLimit::limit(timeout.as_ref(), quote! { #fn_path(#( #forwarded_args ),*) });
+            //the function is called only when the breaker lets the call through
+            let forward_call = if async_fn && circuit_breaker.is_some() {
+                quote! { async move { #forward_call.await } }
+            } else {
+                forward_call
+            };
+            //by default every error is a failure
+            let is_failure = breaker_if.map(|condition| condition.into_token_stream()).unwrap_or_else(|| quote! { |_| true });
+            let forward_call = guard_with_breaker(circuit_breaker.as_ref(), async_fn, forward_call, is_failure);
+            let forward_call = match async_kind {
+                AsyncKind::BoxedFuture if timeout.is_some() || circuit_breaker.is_some() => quote! { Box::pin(#forward_call) },
+                _ => forward_call,
+            };
+            quote! {
                 /**
-                * It is only added to keep the compiler happy for non retryable functions
+                * For non retryable functions it just calls the function, so that statically composed pipelines
+                * can always use the retry function without checking if the function is retryable
                 */
-                pub fn #retry_fn_ident #fn_gen ( #function_mut_args)  #fn_return_type {
-                    panic!("Function not to be called");
+                #[allow(clippy::too_many_arguments)]
+                #helper_vis fn #retry_fn_ident #fn_gen ( #function_mut_args)  #forward_return_type #retry_where_clause {
+                    #forward_call
                 }
-            };
-            let mut token_stream: proc_macro::TokenStream = tokens.into();
-            token_stream.extend(item.into_iter());
-            //println!("{}", toekn_stream.to_string());
-            token_stream
+            }
         }
         SomeRetry(strategy) => {
             let function_args = FunctionArgs { args: arg_tokens };
@@ -272,86 +729,579 @@ pub fn composeable(attr: TokenStream, item: TokenStream) -> TokenStream {
             let deref_mut_tokens: Vec<_> = convert_to_deref_tokens(&function_args);
 
             let strategy_expr = strategy.strategy;
-            let retry_tokens: proc_macro2::TokenStream = if async_fn {
+            //by default every error is retried and failed attempts are not reported
+            let condition = strategy.condition.map(|condition| condition.into_token_stream()).unwrap_or_else(|| quote! { |_| true });
+            let on_retry = strategy.on_retry.map(|on_retry| on_retry.into_token_stream()).unwrap_or_else(|| quote! { |_, _, _| {} });
+            let map_error = if strategy.report_exhausted {
+                quote! { ::function_compose::retry::RetryError::report_exhausted }
+            } else {
+                quote! { ::function_compose::retry::RetryError::into_last_error }
+            };
+            //timeout_ms limits each attempt, deadline_ms all of them
+            let attempt = TimeLimit::limit(timeout.as_ref(), quote! { r });
+            let retried = TimeLimit::limit(strategy.deadline.as_ref(), quote! { async { result.await.map_err(#map_error) } });
+            //the breaker sees the error of the retry loop, by default only errors which exhausted the retries are failures
+            let is_failure = match breaker_if {
+                Some(condition) => quote! {
+                    |error: &::function_compose::retry::RetryError<_>| (#condition)(error.last_error())
+                },
+                None => quote! { ::function_compose::retry::RetryError::is_exhausted },
+            };
+            let retry_result = if let AsyncKind::NativeFuture(_) = async_kind {
+                quote! { result.await }
+            } else {
+                quote! { Box::pin(result) }
+            };
+            let retried_sync = guard_with_breaker(circuit_breaker.as_ref(), async_fn, quote! {
+                ::function_compose::retry::retry_sync(#strategy_expr, ||{
+                    let r:#return_type_without_token = #fn_path(#function_args);
+                    r
+                }, #condition, #on_retry)
+            }, is_failure.clone());
+            let retried_async = guard_with_breaker(circuit_breaker.as_ref(), async_fn, quote! {
+                ::function_compose::retry::retry_async(#strategy_expr, || async{
+                    #( #mutex_unlock_tokens )*;
+                    let r = #fn_path(#( #deref_mut_tokens )*);
+                    #attempt.await
+                }, #condition, #on_retry, ::function_compose::runtime::sleep)
+            }, is_failure);
+            if async_fn {
                 quote! {
 
-                    pub fn #retry_fn_ident #fn_gen(#function_mut_args)  #fn_return_type {
-                        use function_compose::*;
-                        use retry::*;
-                        use tokio_retry::Retry as AsyncRetry;
-                        use tokio::sync::Mutex;
-                        use std::ops::{Deref, DerefMut};
-                        async{
+                    #[allow(clippy::too_many_arguments)]
+                    #helper_vis #retry_asyncness fn #retry_fn_ident #fn_gen(#function_mut_args)  #retry_return_type #retry_where_clause {
+                        use ::function_compose::runtime::Mutex;
+                        use ::std::ops::{Deref, DerefMut};
+                        let result = async move {
                             #( #mutex_tokens )*
-                            let result = AsyncRetry::spawn(#strategy_expr, || async{
-                                #( #mutex_unlock_tokens )*;
-                                let r = #fn_ident(#( #deref_mut_tokens )*);
-                                //OperationResult::from()
-                                r.await
-                            });
-
-                            let result = match result.await{
-                                    Ok(result) => Ok(result),
-                                    Err(e) => Err(e)
-                            };
-                            result
-                        }.boxed()
+                            let result = #retried_async;
+                            #retried.await
+                        };
+                        #retry_result
                     }
                 }
             } else {
                 quote! {
 
-                    pub fn #retry_fn_ident #fn_gen (#function_mut_args)  #fn_return_type {
-                        use function_compose::*;
-                        use retry::*;
-
-                        let result = retry(#strategy_expr, ||{
-                            let r:#return_type_without_token = #fn_ident(#function_args).into();
-                            r
-                        });
-                        match result{
-                            Ok(result) => Ok(result),
-                            Err(e) => Err(e.error)
-                        }
+                    #[allow(clippy::too_many_arguments)]
+                    #helper_vis fn #retry_fn_ident #fn_gen (#function_mut_args)  #fn_return_type #retry_where_clause {
+                        let result = #retried_sync;
+                        result.map_err(#map_error)
                     }
                 }
-            };
+            }
+        }
+    };
 
-            /*println!("#############################");
-            println!("{}", retry_tokens);
-            println!("#############################");*/
+    let helper_tokens = if is_method {
+        let async_fn_ident = helper_ident("is_async");
+        let is_retry_fn_ident = helper_ident("is_retryable");
+        quote! {
+            #lift_tokens
 
-            let tokens: proc_macro2::TokenStream = quote! {
+            #helper_vis fn #async_fn_ident ()  -> bool #self_bound {
+                #async_fn
+            }
 
-                use function_compose::*;
-                pub fn #lift_fn_ident #fun_gen(f: F)  -> #return_type_ident #ret_gen{
-                    //#lift_retry_fn_ident(#retryFnIdent)
-                    #underlying_lift_fn_name_ident(f)
-                }
+            #helper_vis fn #is_retry_fn_ident ()  -> bool #self_bound {
+                #retryable
+            }
 
-                /*pub fn #lift_retry_fn_ident #fun_gen(f: F)  -> #return_type_ident #ret_gen{
-                    #underlying_lift_fn_name_ident(#retryFnIdent)
-                }*/
+            #retry_tokens
 
-                 pub fn #is_retry_fn_ident ()  -> bool{
-                     true
-                }
+            #bound_tokens
+        }
+    } else {
+        let marker_ident = syn::Ident::new(&generate_ident_with_prefix(&fn_name), proc_macro2::Span::call_site());
+        let marker_doc = format!("Marker type grouping the helpers generated by `composeable` for function `{fn_name}`");
+        quote! {
+            #[doc = #marker_doc]
+            #[doc(hidden)]
+            #[allow(non_camel_case_types)]
+            #item_vis struct #marker_ident;
+
+            impl ::function_compose::Composeable for #marker_ident {
+                const ASYNC: bool = #async_fn;
+                const RETRYABLE: bool = #retryable;
+            }
+
+            impl #marker_ident {
+                #lift_tokens
+
+                #retry_tokens
+            }
+        }
+    };
+    let mut token_stream = helper_tokens;
+    token_stream.extend(item);
+    Ok(token_stream)
+}
+
+/// Function referenced in a pipeline, either by name or by path in parenthesis, e.g `(handlers::user::authenticate)`
+struct HelperPath {
+    prefix: syn::Ident,
+    path: syn::ExprPath,
+}
+
+impl Parse for HelperPath {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let prefix = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let path = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            content.parse()?
+        } else {
+            input.parse()?
+        };
+        Ok(HelperPath { prefix, path })
+    }
+}
 
+/// Resolves the helper generated by `composeable` for a function referenced by name or by path.
+///
+/// Helpers of a function are associated with its marker type, `fn_composer_path!(lifted_fn_, (handlers::user::authenticate))`
+/// expands to `handlers::user::fn_composer__authenticate::lifted_fn`. A method is referenced with a qualified path(`<Account>::deposit`,
+/// `<Account as Ledger>::record`) or through `Self`, `fn_composer_path!(lifted_fn_, (<Account>::deposit))` expands to
+/// `<Account>::fn_composer__lifted_fn_deposit`. Any other path refers to a function.
+/// `is_async_` and `is_retryable_` expand to a bool expression.
+/// Type arguments of a generic function(`(to_dto::<Product>)`) are passed only to the retry function, the other helpers
+/// are not generic over the type parameters of the function.
+#[doc(hidden)]
+#[proc_macro]
+pub fn fn_composer_path(item: TokenStream) -> TokenStream {
+    let HelperPath { prefix, mut path } = syn::parse_macro_input!(item as HelperPath);
+    let Some(mut segment) = path.path.segments.pop().map(|pair| pair.into_value()) else {
+        return syn::Error::new_spanned(path, "expected a function").to_compile_error().into();
+    };
+    let type_args = std::mem::replace(&mut segment.arguments, syn::PathArguments::None);
+    let type_args = if prefix == "retry_" { type_args } else { syn::PathArguments::None };
+    let fn_name = segment.ident.to_string();
+    let is_method = path.qself.is_some() || (path.path.segments.len() == 1 && path.path.segments[0].ident == "Self");
+    let parent = match &path.qself {
+        Some(qself) => {
+            let ty = &qself.ty;
+            let trait_segments = path.path.segments.iter();
+            if qself.position > 0 {
+                quote! { <#ty as #(#trait_segments)::*>:: }
+            } else {
+                quote! { <#ty>:: }
+            }
+        }
+        None => path.path.to_token_stream(),
+    };
+    let helper_name = prefix.to_string();
+    let helper_name = helper_name.trim_end_matches('_');
+    let tokens = if is_method {
+        let helper = syn::Ident::new(&generate_ident_with_prefix(&format!("{helper_name}_{fn_name}")), segment.ident.span());
+        match helper_name {
+            "is_async" | "is_retryable" => quote! { #parent #helper() },
+            _ => quote! { #parent #helper #type_args },
+        }
+    } else {
+        let marker = syn::Ident::new(&generate_ident_with_prefix(&fn_name), segment.ident.span());
+        match helper_name {
+            "is_async" => quote! { <#parent #marker as ::function_compose::Composeable>::ASYNC },
+            "is_retryable" => quote! { <#parent #marker as ::function_compose::Composeable>::RETRYABLE },
+            _ => {
+                let helper = syn::Ident::new(helper_name, prefix.span());
+                quote! { #parent #marker::#helper #type_args }
+            }
+        }
+    };
+    tokens.into()
+}
+
+/// Definition of a named pipeline, e.g
+/// `pub async fn auth_flow(request: AuthRequest) -> Result<AuthResponse, AppError> { authenticate -> generate_token }`
+struct PipelineDef {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    sig: syn::Signature,
+    chain: proc_macro2::TokenStream,
+}
+
+impl Parse for PipelineDef {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let sig = input.parse()?;
+        let content;
+        syn::braced!(content in input);
+        let chain = content.parse()?;
+        Ok(PipelineDef { attrs, vis, sig, chain })
+    }
+}
+
+/// Defines a named function from a chain of composeable functions which is itself composeable.
+///
+/// The body is the chain as accepted by `compose!`(without `with_args`), the arguments of the function are passed to the
+/// first function of the chain. The function must be declared async if any function in the chain is async.
+/// The defined function is always composeable, a `#[composeable(..)]` attribute passes its arguments, e.g retry, to it.
+/// A bare `#[composeable]` is the same as no attribute.
+///
+/// ```ignore
+/// pipeline! {
+///     pub async fn auth_flow(request: AuthRequest) -> Result<AuthResponse, AppError> {
+///         authenticate -> generate_token ->> pack_auth_result
+///     }
+/// }
+/// let response = compose!(validate_request -> auth_flow -> with_args(request)).await;
+/// ```
+#[proc_macro]
+pub fn pipeline(item: TokenStream) -> TokenStream {
+    let PipelineDef { attrs, vis, sig, chain } = syn::parse_macro_input!(item as PipelineDef);
+
+    let mut composeable_attr: Option<syn::Attribute> = None;
+    let mut composeable_args = proc_macro2::TokenStream::new();
+    let mut other_attrs = Vec::new();
+    for attr in attrs {
+        if !attr.path().is_ident("composeable") {
+            other_attrs.push(attr);
+            continue;
+        }
+        if composeable_attr.is_some() {
+            return syn::Error::new_spanned(&attr, "pipeline accepts a single composeable attribute").to_compile_error().into();
+        }
+        match &attr.meta {
+            syn::Meta::List(list) => composeable_args = list.tokens.clone(),
+            syn::Meta::Path(_) => {}
+            syn::Meta::NameValue(_) => {
+                return syn::Error::new_spanned(&attr, "expected `#[composeable]` or `#[composeable(..)]`").to_compile_error().into();
+            }
+        }
+        composeable_attr = Some(attr);
+    }
+
+    let mut arg_names = Vec::new();
+    for arg in &sig.inputs {
+        match arg {
+            FnArg::Typed(pat_type) => match pat_type.pat.as_ref() {
+                syn::Pat::Ident(pat_ident) => arg_names.push(pat_ident.ident.clone()),
+                pat => return syn::Error::new_spanned(pat, "pipeline arguments must be plain identifiers").to_compile_error().into(),
+            },
+            FnArg::Receiver(receiver) => {
+                return syn::Error::new_spanned(receiver, "pipeline cannot take self").to_compile_error().into();
+            }
+        }
+    }
+    if arg_names.is_empty() {
+        return syn::Error::new_spanned(&sig, "pipeline must take at least one argument").to_compile_error().into();
+    }
+
+    let awaited = if sig.asyncness.is_some() { quote! { .await } } else { quote! {} };
+    let item_fn = quote! {
+        #( #other_attrs )*
+        #vis #sig {
+            ::function_compose::compose!(#chain -> with_args(#( #arg_names ),*)) #awaited
+        }
+    };
+    composeable(composeable_args.into(), item_fn.into())
+}
+
+/// Generates the lifted functions(boxed, shared and step) of a function with injected arguments.
+///
+/// The lifted functions accept only the arguments which are not injected. The injected arguments are resolved on every call
+/// from the container which is current(see Container::scope) when the function is composed. Without a current container
+/// every call fails with ContainerError::NoContainer.
+/// Argument `f` of the lifted functions is ignored, the retry function(which forwards to the function if it is not retryable) is always called.
+fn injected_lift_fns(
+    sig: &syn::Signature,
+    inject: &[syn::Ident],
+    async_kind: &AsyncKind,
+    vis: &proc_macro2::TokenStream,
+    [lift_fn_ident, lift_shared_fn_ident, lift_step_fn_ident]: [&syn::Ident; 3],
+    retry_fn_ident: &syn::Ident,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut call_args = Vec::new();
+    let mut remaining_args = Vec::new();
+    let mut remaining_types = Vec::new();
+    let mut injected = Vec::new();
+    for (index, arg) in sig.inputs.iter().enumerate() {
+        let pat_type = match arg {
+            FnArg::Typed(pat_type) => pat_type,
+            FnArg::Receiver(receiver) => return Err(syn::Error::new_spanned(receiver, "functions with self receiver cannot inject arguments")),
+        };
+        let injected_ident = match pat_type.pat.as_ref() {
+            syn::Pat::Ident(pat_ident) if inject.contains(&pat_ident.ident) => Some(&pat_ident.ident),
+            _ => None,
+        };
+        match injected_ident {
+            Some(ident) => {
+                let var = syn::Ident::new(&format!("injected_{ident}"), proc_macro2::Span::call_site());
+                let (resolved_type, call_arg) = match pat_type.ty.as_ref() {
+                    Type::Reference(reference) if reference.mutability.is_some() => (reference.elem.as_ref(), quote! { &mut #var }),
+                    Type::Reference(reference) => (reference.elem.as_ref(), quote! { &#var }),
+                    ty => (ty, quote! { #var }),
+                };
+                injected.push((var, resolved_type.clone()));
+                call_args.push(call_arg);
+            }
+            None => {
+                let arg_ident = syn::Ident::new(&format!("t{}", index + 1), proc_macro2::Span::call_site());
+                call_args.push(quote! { #arg_ident });
+                remaining_args.push(arg_ident);
+                remaining_types.push(pat_type.ty.as_ref().clone());
+            }
+        }
+    }
+    if let Some(unknown) = inject.iter().find(|ident| !injected.iter().any(|(var, _)| *var == format!("injected_{ident}"))) {
+        return Err(syn::Error::new_spanned(unknown, format!("`{unknown}` is not an argument of function `{}`", sig.ident)));
+    }
+    if remaining_args.is_empty() {
+        return Err(syn::Error::new_spanned(&sig.ident, "at least one argument of a function with injected arguments must not be injected"));
+    }
 
-                pub fn #async_fn_ident ()  -> bool{
-                    #async_fn
+    let output = result_type(sig, async_kind)?;
+    let ok_type = quote! { <#output as ::function_compose::FnResult>::Ok };
+    let err_type = quote! { <#output as ::function_compose::FnResult>::Err };
+
+    let mut generics = sig.generics.clone();
+    let is_generic = generics.params.iter().any(|param| !matches!(param, syn::GenericParam::Lifetime(_)));
+    generics.params.insert(0, syn::parse_quote! { 'fn_composer_a });
+    generics.params.push(syn::parse_quote! { F });
+    let fn_arg_types = fn_arg_types_with_named_lifetimes(sig);
+    if is_generic && fn_arg_types.is_some() {
+        generics.params.push(syn::parse_quote! { FnComposerOut });
+    }
+    let where_clause = generics.make_where_clause();
+    for ty in &remaining_types {
+        where_clause.predicates.push(syn::parse_quote! { #ty: Send + 'fn_composer_a });
+    }
+    //type parameters of a generic function are inferred from the function passed as `_f`, e.g. `scale::<i8>`
+    if let Some(fn_arg_types) = fn_arg_types.filter(|_| is_generic) {
+        where_clause.predicates.push(syn::parse_quote! { F: Fn(#( #fn_arg_types ),*) -> FnComposerOut });
+    }
+    where_clause.predicates.push(syn::parse_quote! { #output: ::function_compose::FnResult });
+    where_clause.predicates.push(syn::parse_quote! { #ok_type: Send + 'fn_composer_a });
+    where_clause.predicates.push(syn::parse_quote! { #err_type: From<::function_compose::ContainerError> + Send + 'fn_composer_a });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let arg_count = remaining_args.len();
+    let async_fn = !matches!(async_kind, AsyncKind::Sync);
+    let (boxed_type, shared_type, step_type) = if async_fn {
+        (format!("BoxedAsyncFn{arg_count}"), format!("SharedAsyncFn{arg_count}"), "AsyncStep")
+    } else {
+        (format!("BoxedFn{arg_count}"), format!("SharedFn{arg_count}"), "SyncStep")
+    };
+    let boxed_type = syn::Ident::new(&boxed_type, proc_macro2::Span::call_site());
+    let shared_type = syn::Ident::new(&shared_type, proc_macro2::Span::call_site());
+    let step_type = syn::Ident::new(step_type, proc_macro2::Span::call_site());
+
+    let injected_vars: Vec<_> = injected.iter().map(|(var, _)| var).collect();
+    let injected_types: Vec<_> = injected.iter().map(|(_, ty)| ty).collect();
+    let closure_args = quote! { #( #remaining_args: #remaining_types ),* };
+    let turbofish = turbofish(sig);
+    let call = quote! { Self::#retry_fn_ident #turbofish(#( #call_args ),*) };
+    //the closure resolving the injected arguments and calling the function. Async closures return an unboxed future
+    let closure = if async_fn {
+        quote! {
+            move |#closure_args| {
+                let container = container.clone();
+                async move {
+                    let container = match container {
+                        Ok(container) => container,
+                        Err(error) => return Err(From::from(error)),
+                    };
+                    #(
+                        #[allow(unused_mut)]
+                        let mut #injected_vars = match container.resolve_async::<#injected_types>().await {
+                            Ok(value) => value,
+                            Err(error) => return Err(From::from(error)),
+                        };
+                    )*
+                    ::function_compose::FnResult::into_result(#call.await)
                 }
-            };
-            let retry_token_stream: TokenStream = retry_tokens.into();
+            }
+        }
+    } else {
+        quote! {
+            move |#closure_args| {
+                let container = match &container {
+                    Ok(container) => container,
+                    Err(error) => return Err(From::from(error.clone())),
+                };
+                #(
+                    #[allow(unused_mut)]
+                    let mut #injected_vars = match container.resolve::<#injected_types>() {
+                        Ok(value) => value,
+                        Err(error) => return Err(From::from(error)),
+                    };
+                )*
+                ::function_compose::FnResult::into_result(#call)
+            }
+        }
+    };
+    let boxed_closure = if async_fn {
+        quote! {
+            {
+                let f = #closure;
+                move |#closure_args| -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = Result<#ok_type, #err_type>> + Send + 'fn_composer_a>> {
+                    Box::pin(f(#( #remaining_args ),*))
+                }
+            }
+        }
+    } else {
+        closure.clone()
+    };
+
+    Ok(quote! {
+        #[allow(clippy::multiple_bound_locations)]
+        #vis fn #lift_fn_ident #impl_generics(_f: F) -> ::function_compose::#boxed_type<'fn_composer_a, #( #remaining_types, )* #ok_type, #err_type> #where_clause {
+            let container = ::function_compose::Container::current();
+            Box::new(#boxed_closure)
+        }
+
+        #[allow(clippy::multiple_bound_locations)]
+        #vis fn #lift_shared_fn_ident #impl_generics(_f: F) -> ::function_compose::#shared_type<'fn_composer_a, #( #remaining_types, )* #ok_type, #err_type> #where_clause {
+            let container = ::function_compose::Container::current();
+            ::std::sync::Arc::new(#boxed_closure)
+        }
+
+        #[allow(clippy::multiple_bound_locations)]
+        #vis fn #lift_step_fn_ident #impl_generics(_f: F) -> impl ::function_compose::Step<Input = (#( #remaining_types, )*), Output = #ok_type, Error = #err_type> + 'fn_composer_a #where_clause {
+            let container = ::function_compose::Container::current();
+            ::function_compose::#step_type::<_, (#( #remaining_types, )*)>::new(#closure)
+        }
+    })
+}
+
+/// Type and const parameters of a function as turbofish(`::<T, N>`), so that the generated helpers forward them to the function.
+/// Empty if the function has no such parameters or declares an argument as `impl Trait`, which does not allow explicit generic arguments.
+fn turbofish(sig: &syn::Signature) -> proc_macro2::TokenStream {
+    let impl_trait_arg = sig.inputs.iter().any(|arg| matches!(arg, FnArg::Typed(pat_type) if matches!(pat_type.ty.as_ref(), Type::ImplTrait(_))));
+    let params: Vec<_> = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(type_param) => Some(&type_param.ident),
+            syn::GenericParam::Const(const_param) => Some(&const_param.ident),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    if params.is_empty() || impl_trait_arg {
+        quote! {}
+    } else {
+        quote! { ::<#( #params ),*> }
+    }
+}
+
+/// Replaces elided lifetimes(`&T`, `'_`) with `'fn_composer_a`
+struct NameElidedLifetimes;
+
+impl syn::visit_mut::VisitMut for NameElidedLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = syn::parse_quote! { 'fn_composer_a };
+        }
+    }
 
-            let mut token_stream: proc_macro::TokenStream = tokens.into();
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(syn::parse_quote! { 'fn_composer_a });
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+}
+
+/// Argument types of a function with elided lifetimes named, so that they can be used in a `Fn(..)` bound which is not higher ranked.
+/// None if an argument is declared as `impl Trait`.
+fn fn_arg_types_with_named_lifetimes(sig: &syn::Signature) -> Option<Vec<Type>> {
+    sig.inputs
+        .iter()
+        .map(|arg| match arg {
+            FnArg::Typed(pat_type) if !matches!(pat_type.ty.as_ref(), Type::ImplTrait(_)) => {
+                let mut ty = pat_type.ty.as_ref().clone();
+                syn::visit_mut::VisitMut::visit_type_mut(&mut NameElidedLifetimes, &mut ty);
+                Some(ty)
+            }
+            _ => None,
+        })
+        .collect()
+}
 
-            token_stream.extend(item.into_iter());
-            token_stream.extend(retry_token_stream.into_iter());
-            /*println!("{}", toekn_stream.to_string());*/
-            token_stream
+/// Result type of a composeable function, i.e the Output of the future returned by async functions
+fn result_type(sig: &syn::Signature, async_kind: &AsyncKind) -> syn::Result<Type> {
+    match (async_kind, &sig.output) {
+        (AsyncKind::NativeFuture(output), _) => Ok(output.clone()),
+        (AsyncKind::BoxedFuture, ReturnType::Type(_, ty)) => boxed_future_output(ty).ok_or_else(|| syn::Error::new_spanned(ty, "cannot find Output type of the future")),
+        (_, ReturnType::Type(_, ty)) => Ok(ty.as_ref().clone()),
+        (_, ReturnType::Default) => Err(syn::Error::new_spanned(sig, "composeable function must return Result")),
+    }
+}
+
+/// Generates the methods lifting a composeable method with its receiver bound, so that `value.method` can be composed.
+///
+/// `sig` is the signature of the method with the receiver replaced by `fn_composer_self`(see receiver_as_typed_arg).
+/// The lifted function accepts the remaining arguments of the method and calls its retry function.
+/// Methods taking only the receiver have no bound methods.
+fn bound_method_fns(
+    sig: &syn::Signature,
+    receiver: &syn::Receiver,
+    async_kind: &AsyncKind,
+    vis: &proc_macro2::TokenStream,
+    [bound_fn_ident, bound_step_fn_ident]: [&syn::Ident; 2],
+    retry_fn_ident: &syn::Ident,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let bound_receiver = match (&receiver.reference, &receiver.mutability) {
+        (Some(_), Some(_)) => quote! { &'fn_composer_a mut self },
+        (Some(_), None) => quote! { &'fn_composer_a self },
+        (None, _) => quote! { self },
+    };
+    let mut args = Vec::new();
+    let mut arg_types = Vec::new();
+    for (index, arg) in sig.inputs.iter().enumerate().skip(1) {
+        if let FnArg::Typed(pat_type) = arg {
+            args.push(syn::Ident::new(&format!("t{index}"), proc_macro2::Span::call_site()));
+            arg_types.push(pat_type.ty.as_ref().clone());
         }
     }
+    if args.is_empty() {
+        return Ok(quote! {});
+    }
+
+    let output = result_type(sig, async_kind)?;
+    let ok_type = quote! { <#output as ::function_compose::FnResult>::Ok };
+    let err_type = quote! { <#output as ::function_compose::FnResult>::Err };
+
+    let mut generics = sig.generics.clone();
+    generics.params.insert(0, syn::parse_quote! { 'fn_composer_a });
+    let where_clause = generics.make_where_clause();
+    where_clause.predicates.push(syn::parse_quote! { Self: Sized + Send + Sync + 'fn_composer_a });
+    for ty in &arg_types {
+        where_clause.predicates.push(syn::parse_quote! { #ty: 'fn_composer_a });
+    }
+    where_clause.predicates.push(syn::parse_quote! { #output: ::function_compose::FnResult });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let arg_count = args.len();
+    //the receiver is moved into a local before the call, passing `self` directly reborrows it which makes the closure FnMut
+    let turbofish = turbofish(sig);
+    let call = quote! { Self::#retry_fn_ident #turbofish(receiver, #( #args ),*) };
+    let (boxed_type, step_type, boxed_call) = match async_kind {
+        AsyncKind::Sync => (format!("BoxedFn{arg_count}"), "SyncStep", call.clone()),
+        AsyncKind::BoxedFuture => (format!("BoxedAsyncFn{arg_count}"), "AsyncStep", call.clone()),
+        AsyncKind::NativeFuture(_) => (format!("BoxedAsyncFn{arg_count}"), "AsyncStep", quote! { Box::pin(#call) }),
+    };
+    let boxed_type = syn::Ident::new(&boxed_type, proc_macro2::Span::call_site());
+    let step_type = syn::Ident::new(step_type, proc_macro2::Span::call_site());
+
+    Ok(quote! {
+        #vis fn #bound_fn_ident #impl_generics(#bound_receiver) -> ::function_compose::#boxed_type<'fn_composer_a, #( #arg_types, )* #ok_type, #err_type> #where_clause {
+            Box::new(move |#( #args: #arg_types ),*| {
+                let receiver = self;
+                #boxed_call
+            })
+        }
+
+        #vis fn #bound_step_fn_ident #impl_generics(#bound_receiver) -> impl ::function_compose::Step<Input = (#( #arg_types, )*), Output = #ok_type, Error = #err_type> + 'fn_composer_a #where_clause {
+            ::function_compose::#step_type::<_, (#( #arg_types, )*)>::new(move |#( #args: #arg_types ),*| {
+                let receiver = self;
+                #call
+            })
+        }
+    })
 }
 
 fn filter_mutable_args<'a>(function_args: &'a FunctionArgs) -> Vec<&'a &'a FnArg> {
@@ -381,7 +1331,7 @@ fn convert_to_create_mutex_tokens(mutable_args: &Vec<&&FnArg>) -> Vec<proc_macro
         .iter()
         .map(|i| match i {
             FnArg::Receiver(_pat_type) => {
-                panic!();
+                unreachable!("the receiver of a method is passed as the typed argument fn_composer_self");
             }
             FnArg::Typed(pat_type) => {
                 let pat = &pat_type.pat;
@@ -398,7 +1348,7 @@ fn convert_to_mutex_unlock_tokens(mutable_args: Vec<&&FnArg>) -> Vec<proc_macro2
         .iter()
         .map(|i| match i {
             FnArg::Receiver(_pat_type) => {
-                panic!();
+                unreachable!("the receiver of a method is passed as the typed argument fn_composer_self");
             }
             FnArg::Typed(pat_type) => {
                 let pat = &pat_type.pat;
@@ -411,6 +1361,38 @@ fn convert_to_mutex_unlock_tokens(mutable_args: Vec<&&FnArg>) -> Vec<proc_macro2
         .collect()
 }
 
+/// Guards the Result of a sync function, or the future of the Result of an async function, with the named circuit breaker,
+/// which counts the errors `is_failure` holds for as failures. The breaker is looked up once per function and kept in a static, so that calls don't lock the registry of breakers
+fn guard_with_breaker(
+    breaker: Option<&syn::LitStr>,
+    async_fn: bool,
+    call: proc_macro2::TokenStream,
+    is_failure: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let Some(name) = breaker else {
+        return call;
+    };
+    let guarded_call = if async_fn {
+        quote! { ::std::sync::Arc::clone(&BREAKER).call_if(#call, #is_failure) }
+    } else {
+        quote! { BREAKER.call_sync_if(|| #call, #is_failure) }
+    };
+    quote! {
+        {
+            static BREAKER: ::std::sync::LazyLock<::std::sync::Arc<::function_compose::CircuitBreaker>> =
+                ::std::sync::LazyLock::new(|| ::function_compose::CircuitBreaker::named(#name));
+            #guarded_call
+        }
+    }
+}
+
+/// Clones an owned argument for an attempt of a retried function, a missing Clone implementation is reported at the type of the argument
+fn clone_for_retry(pat: &syn::Pat, ty: &Type) -> proc_macro2::TokenStream {
+    quote::quote_spanned! {ty.span()=>
+        ::function_compose::retry::clone_for_retry(&#pat)
+    }
+}
+
 fn convert_to_deref_tokens(function_args: &FunctionArgs) -> Vec<proc_macro2::TokenStream> {
     function_args
         .args
@@ -437,15 +1419,12 @@ fn convert_to_deref_tokens(function_args: &FunctionArgs) -> Vec<proc_macro2::Tok
                         }
 
                         _ => {
-                            return quote! {
-                                #pat,
-                            };
+                            let cloned = clone_for_retry(pat, ty);
+                            quote! {
+                                #cloned,
+                            }
                         }
                     }
-                    /*quote!{
-                        let mut #pat = #pat.lock().await;
-
-                    }*/
                 }
             }
         })
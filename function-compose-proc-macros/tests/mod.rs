@@ -1,8 +1,8 @@
 use futures::{future::BoxFuture, FutureExt};
-use retry::delay::*;
+use function_compose::retry::*;
 
 
-use function_compose::composeable;
+use function_compose::{composeable, Composeable};
 #[derive(Debug)]
 pub struct FnError<E>{
     pub underlying_error: Option<E>,
@@ -54,49 +54,58 @@ pub fn add_async<'l>(a: &'l mut i32, b: &'l mut i32) -> BoxFuture<'l, Result<i32
 
 #[test]
 fn composeable_sync_fn_retryable_test() {
-    let lifted_add = fn_composer__lifted_fn_add_retryable(add_retryable);
+    let lifted_add = fn_composer__add_retryable::lifted_fn(add_retryable);
 
-    let is_add_async = fn_composer__is_async_add_retryable();
-    let is_retryable = fn_composer__is_retryable_add_retryable();
-    let _retry_fn = fn_composer__retry_add_retryable;
-    assert_eq!(is_add_async, false);
-    assert_eq!(is_retryable, true);
+    let is_add_async = <fn_composer__add_retryable as Composeable>::ASYNC;
+    let is_retryable = <fn_composer__add_retryable as Composeable>::RETRYABLE;
+    let _retry_fn = fn_composer__add_retryable::retry;
+    assert!(!is_add_async);
+    assert!(is_retryable);
     let _  = lifted_add(&10, &mut 11);
 }
 
 #[test]
 fn composeable_sync_fn_test() {
-    let lifted_add = fn_composer__lifted_fn_add_retryable(add);
+    let lifted_add = fn_composer__add_retryable::lifted_fn(add);
 
-    let is_add_async = fn_composer__is_async_add();
-    let is_retryable = fn_composer__is_retryable_add();
-    let _retry_fn = fn_composer__retry_add_retryable;
-    assert_eq!(is_add_async, false);
-    assert_eq!(is_retryable, false);
+    let is_add_async = <fn_composer__add as Composeable>::ASYNC;
+    let is_retryable = <fn_composer__add as Composeable>::RETRYABLE;
+    let _retry_fn = fn_composer__add_retryable::retry;
+    assert!(!is_add_async);
+    assert!(!is_retryable);
     let _ = lifted_add(&10, &mut 11);
 }
 
+#[test]
+fn composeable_shared_sync_fn_test() {
+    let (mut b1, mut b2) = (11, 12);
+    let shared_add = fn_composer__add::lifted_shared_fn(add);
+    let cloned_add = shared_add.clone();
+    assert_eq!(21, shared_add(&10, &mut b1).unwrap());
+    assert_eq!(22, cloned_add(&10, &mut b2).unwrap());
+}
+
 #[test]
 fn composeable_async_fn_test() {
-    let _lifted_add = fn_composer__lifted_fn_add_async(add_async);
+    let _lifted_add = fn_composer__add_async::lifted_fn(add_async);
 
-    let is_add_async = fn_composer__is_async_add_async();
-    let is_retryable = fn_composer__is_retryable_add_async();
-    let _retry_fn = fn_composer__retry_add_async;
-    assert_eq!(is_retryable, false);
-    assert_eq!(is_add_async, true);
+    let is_add_async = <fn_composer__add_async as Composeable>::ASYNC;
+    let is_retryable = <fn_composer__add_async as Composeable>::RETRYABLE;
+    let _retry_fn = fn_composer__add_async::retry;
+    assert!(!is_retryable);
+    assert!(is_add_async);
 }
 
 #[test]
 fn composeable_async_fn_retryable_test() {
-    let _lifted_add = fn_composer__lifted_fn_add_async_retryable(add_async_retryable);
+    let _lifted_add = fn_composer__add_async_retryable::lifted_fn(add_async_retryable);
 
-    let is_add_async = fn_composer__is_async_add_async_retryable();
-    let is_retryable = fn_composer__is_retryable_add_async_retryable();
+    let is_add_async = <fn_composer__add_async_retryable as Composeable>::ASYNC;
+    let is_retryable = <fn_composer__add_async_retryable as Composeable>::RETRYABLE;
 
-    let _retry_fn = fn_composer__retry_add_async_retryable;
-    assert_eq!(is_retryable, true);
-    assert_eq!(is_add_async, true);
+    let _retry_fn = fn_composer__add_async_retryable::retry;
+    assert!(is_retryable);
+    assert!(is_add_async);
 }
 
 #[composeable(retry = Fixed::from_millis(100))]
@@ -124,3 +133,9 @@ pub fn add_3_arg_ref_non_copy_async<'a>(
     }
     .boxed()
 }
+
+#[test]
+fn composeable_rejected_signatures() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
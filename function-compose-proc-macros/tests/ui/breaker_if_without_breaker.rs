@@ -0,0 +1,12 @@
+use function_compose::composeable;
+
+fn is_transient(error: &String) -> bool {
+    error.starts_with("timeout")
+}
+
+#[composeable(breaker_if = is_transient)]
+pub fn add_10(a: i32) -> Result<i32, String> {
+    Ok(a + 10)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable(circuit_breaker = stock)]
+pub fn check_stock(quantity: i32) -> Result<i32, String> {
+    Ok(quantity)
+}
+
+fn main() {}
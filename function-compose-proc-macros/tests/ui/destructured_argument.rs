@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable()]
+pub fn add((a, b): (i32, i32)) -> Result<i32, String> {
+    Ok(a + b)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable(retry = function_compose::retry::Fixed::from_millis(10).take(1), retry = function_compose::retry::Fixed::from_millis(20).take(1))]
+pub fn add_10(a: i32) -> Result<i32, String> {
+    Ok(a + 10)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable()]
+pub fn describe(a: i32) -> impl std::fmt::Display {
+    a
+}
+
+fn main() {}
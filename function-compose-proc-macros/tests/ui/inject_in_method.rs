@@ -0,0 +1,12 @@
+use function_compose::composeable;
+
+pub struct Counter(i32);
+
+impl Counter {
+    #[composeable(inject(a))]
+    pub fn add(&self, a: i32) -> Result<i32, String> {
+        Ok(self.0 + a)
+    }
+}
+
+fn main() {}
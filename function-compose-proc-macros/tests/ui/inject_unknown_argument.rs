@@ -0,0 +1,13 @@
+use function_compose::composeable;
+
+#[derive(Clone)]
+pub struct Settings {
+    pub increment: i32,
+}
+
+#[composeable(inject(config))]
+pub fn add_setting(a: i32, settings: Settings) -> Result<i32, String> {
+    Ok(a + settings.increment)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable()]
+pub fn answer() -> Result<i32, String> {
+    Ok(42)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable()]
+pub fn add_10(a: i32) {
+    let _ = a + 10;
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable()]
+pub async fn add_10(a: i32) -> Option<i32> {
+    Some(a + 10)
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use function_compose::{composeable, pipeline};
+
+#[composeable()]
+fn add_10(a: i32) -> Result<i32, String> {
+    Ok(a + 10)
+}
+
+pipeline! {
+    #[composeable = "retry"]
+    fn add_20(a: i32) -> Result<i32, String> {
+        add_10 -> add_10
+    }
+}
+
+fn main() {}
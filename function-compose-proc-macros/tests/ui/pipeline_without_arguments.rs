@@ -0,0 +1,14 @@
+use function_compose::*;
+
+#[composeable()]
+pub fn add_10(a: i32) -> Result<i32, String> {
+    Ok(a + 10)
+}
+
+pipeline! {
+    pub fn add_20() -> Result<i32, String> {
+        add_10 -> add_10
+    }
+}
+
+fn main() {}
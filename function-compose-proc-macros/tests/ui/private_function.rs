@@ -0,0 +1,19 @@
+use function_compose::{compose, composeable};
+
+mod temperatures {
+    use function_compose::composeable;
+
+    #[composeable()]
+    fn to_kelvin(celsius: f64) -> Result<f64, String> {
+        Ok(celsius + 273.15)
+    }
+}
+
+#[composeable()]
+fn add_10(a: f64) -> Result<f64, String> {
+    Ok(a + 10.0)
+}
+
+fn main() {
+    let _ = compose!(add_10 -> temperatures::to_kelvin -> with_args(1.0));
+}
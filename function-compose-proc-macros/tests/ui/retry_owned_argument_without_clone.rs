@@ -0,0 +1,13 @@
+use function_compose::composeable;
+use function_compose::retry::Fixed;
+
+pub struct Order {
+    pub id: u32,
+}
+
+#[composeable(retry = Fixed::from_millis(100).take(3))]
+pub fn submit_order(order: Order) -> Result<u32, String> {
+    Ok(order.id)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable(timeout_ms = 500)]
+pub fn add_10(a: i32) -> Result<i32, String> {
+    Ok(a + 10)
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use function_compose::composeable;
+
+#[composeable()]
+pub unsafe fn read(a: *const i32) -> Result<i32, String> {
+    Ok(unsafe { *a })
+}
+
+fn main() {}
@@ -0,0 +1,122 @@
+//! Conditional branching between two functions.
+//!
+//! The predicate is called with a reference to the input. If it returns true the input is passed to the first(then) function,
+//! otherwise to the second(else) function. Both functions must have the same output and error type.
+//! If both the functions are sync the result is sync, otherwise the result is an async function.
+
+use std::sync::Arc;
+
+use crate::{
+    BoxedAsyncFn1, BoxedFn1, IntoBoxedAsyncFn1, IntoSharedAsyncFn1, SharedAsyncFn1, SharedFn1, Step,
+};
+
+/// Combines a pair of (then, else) functions into a single function choosing one of them using predicate P.
+///
+/// Type param R is the resulting function type
+pub trait Branch<'a, A, P, R> {
+    fn branch(self, pred: P) -> R;
+}
+
+/// Builds a function calling then_fn if pred returns true for the input and else_fn otherwise.
+///
+/// Works for both boxed and shared functions, see [`Branch`]. For shared functions the predicate must implement Fn.
+pub fn branch<'a, A, P, F1, F2, R>(pred: P, then_fn: F1, else_fn: F2) -> R
+where
+    P: FnOnce(&A) -> bool,
+    (F1, F2): Branch<'a, A, P, R>,
+{
+    (then_fn, else_fn).branch(pred)
+}
+
+impl<'a, A: 'a, B: 'a, E: 'a, P> Branch<'a, A, P, BoxedFn1<'a, A, B, E>> for (BoxedFn1<'a, A, B, E>, BoxedFn1<'a, A, B, E>)
+where
+    P: FnOnce(&A) -> bool + Send + Sync + 'a,
+{
+    fn branch(self, pred: P) -> BoxedFn1<'a, A, B, E> {
+        let (then_fn, else_fn) = self;
+        Box::new(move |a: A| if pred(&a) { then_fn(a) } else { else_fn(a) })
+    }
+}
+
+impl<'a, A: 'a, B: 'a, E: 'a, P> Branch<'a, A, P, SharedFn1<'a, A, B, E>> for (SharedFn1<'a, A, B, E>, SharedFn1<'a, A, B, E>)
+where
+    P: Fn(&A) -> bool + Send + Sync + 'a,
+{
+    fn branch(self, pred: P) -> SharedFn1<'a, A, B, E> {
+        let (then_fn, else_fn) = self;
+        Arc::new(move |a: A| if pred(&a) { then_fn(a) } else { else_fn(a) })
+    }
+}
+
+macro_rules! impl_async_branch {
+    ($then_type:ident, $else_type:ident) => {
+        #[doc = concat!("Branch implementation for choosing between ", stringify!($then_type), " and ", stringify!($else_type))]
+        impl<'a, A: Send + 'a, B: Send + 'a, E: Send + 'a, P> Branch<'a, A, P, BoxedAsyncFn1<'a, A, B, E>> for ($then_type<'a, A, B, E>, $else_type<'a, A, B, E>)
+        where
+            P: FnOnce(&A) -> bool + Send + Sync + 'a,
+        {
+            fn branch(self, pred: P) -> BoxedAsyncFn1<'a, A, B, E> {
+                let then_fn = self.0.into_boxed_async_fn1();
+                let else_fn = self.1.into_boxed_async_fn1();
+                Box::new(move |a: A| if pred(&a) { then_fn(a) } else { else_fn(a) })
+            }
+        }
+    };
+}
+
+macro_rules! impl_shared_async_branch {
+    ($then_type:ident, $else_type:ident) => {
+        #[doc = concat!("Branch implementation for choosing between ", stringify!($then_type), " and ", stringify!($else_type))]
+        impl<'a, A: Send + 'a, B: Send + 'a, E: Send + 'a, P> Branch<'a, A, P, SharedAsyncFn1<'a, A, B, E>> for ($then_type<'a, A, B, E>, $else_type<'a, A, B, E>)
+        where
+            P: Fn(&A) -> bool + Send + Sync + 'a,
+        {
+            fn branch(self, pred: P) -> SharedAsyncFn1<'a, A, B, E> {
+                let then_fn = self.0.into_shared_async_fn1();
+                let else_fn = self.1.into_shared_async_fn1();
+                Arc::new(move |a: A| if pred(&a) { then_fn(a) } else { else_fn(a) })
+            }
+        }
+    };
+}
+
+impl_async_branch!(BoxedFn1, BoxedAsyncFn1);
+impl_async_branch!(BoxedAsyncFn1, BoxedFn1);
+impl_async_branch!(BoxedAsyncFn1, BoxedAsyncFn1);
+
+impl_shared_async_branch!(SharedFn1, SharedAsyncFn1);
+impl_shared_async_branch!(SharedAsyncFn1, SharedFn1);
+impl_shared_async_branch!(SharedAsyncFn1, SharedAsyncFn1);
+
+/// Step calling either then_step or else_step based on the predicate, the statically typed counterpart of [`Branch`]
+#[derive(Clone, Copy)]
+pub struct Branched<P, S1, S2> {
+    pred: P,
+    then_step: S1,
+    else_step: S2,
+}
+
+impl<P, S1, S2> Branched<P, S1, S2> {
+    pub fn new(pred: P, then_step: S1, else_step: S2) -> Self {
+        Branched { pred, then_step, else_step }
+    }
+}
+
+impl<A, P, S1, S2> Step for Branched<P, S1, S2>
+where
+    P: FnOnce(&A) -> bool,
+    S1: Step<Input = (A,)>,
+    S2: Step<Input = (A,), Output = S1::Output, Error = S1::Error>,
+{
+    type Input = (A,);
+    type Output = S1::Output;
+    type Error = S1::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        if (self.pred)(&input.0) {
+            self.then_step.call(input).await
+        } else {
+            self.else_step.call(input).await
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! Named circuit breakers of composeable functions.
+//!
+//! A function marked with `#[composeable(circuit_breaker = "name")]` is guarded by the breaker registered under the name,
+//! functions using the same name share the breaker. The breaker counts the outcome of every call(after all retries) and opens
+//! when the ratio of failed calls reaches the threshold. Only errors for which `breaker_if` holds are failures, by default every
+//! error of a function without retry and the errors of a retried function which exhausted the retries(those `retry_if` holds for),
+//! other errors are counted as successful calls since the dependency answered. While open, calls fail with [`CircuitOpen`] converted into the error type
+//! of the function without calling it. After the cool-down the breaker is half-open and lets a single trial call through,
+//! which closes the breaker when it succeeds and opens it again when it fails.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::retry::RetryError;
+
+/// Error of a function whose circuit breaker is open, the function was not called
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitOpen {
+    /// name of the breaker
+    pub name: String,
+    /// time until the breaker lets a trial call through, zero while a trial call is in progress
+    pub retry_after: Duration,
+}
+
+impl Display for CircuitOpen {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "circuit breaker {} is open, retry after {:?}", self.name, self.retry_after)
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+/// An open breaker aborts the retry loop of the function it guards
+impl<E: From<CircuitOpen>> From<CircuitOpen> for RetryError<E> {
+    fn from(open: CircuitOpen) -> Self {
+        RetryError::Aborted(E::from(open))
+    }
+}
+
+/// State of a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// calls are let through and their outcomes counted
+    Closed,
+    /// calls fail with [`CircuitOpen`] until the cool-down elapses
+    Open,
+    /// a single trial call is let through to decide if the breaker closes
+    HalfOpen,
+}
+
+/// Thresholds of a [`CircuitBreaker`]
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// ratio of failed calls in the window which opens the breaker, above 0 and at most 1
+    pub failure_ratio: f64,
+    /// number of most recent calls the failure ratio is computed from
+    pub window: usize,
+    /// number of calls in the window required before the breaker can open, between 1 and the window
+    pub minimum_calls: usize,
+    /// time the breaker stays open before it lets a trial call through
+    pub cool_down: Duration,
+}
+
+/// Invalid [`CircuitBreakerConfig`], such a breaker would open without failures or never open
+#[derive(Debug, Clone, PartialEq)]
+pub enum CircuitBreakerConfigError {
+    /// the failure ratio is not above 0 and at most 1
+    FailureRatio { name: String, failure_ratio: f64 },
+    /// `minimum_calls` is 0 or exceeds the window
+    MinimumCalls { name: String, minimum_calls: usize, window: usize },
+}
+
+impl Display for CircuitBreakerConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitBreakerConfigError::FailureRatio { name, failure_ratio } => {
+                write!(f, "failure_ratio of circuit breaker {name} must be above 0 and at most 1, found {failure_ratio}")
+            }
+            CircuitBreakerConfigError::MinimumCalls { name, minimum_calls, window } => {
+                write!(f, "minimum_calls of circuit breaker {name} must be at least 1 and at most the window {window}, found {minimum_calls}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CircuitBreakerConfigError {}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig { failure_ratio: 0.5, window: 20, minimum_calls: 5, cool_down: Duration::from_secs(30) }
+    }
+}
+
+struct Circuit {
+    config: CircuitBreakerConfig,
+    state: CircuitState,
+    opened_at: Instant,
+    /// outcomes of the calls in the window, true for a failed call
+    outcomes: VecDeque<bool>,
+    trial_in_progress: bool,
+}
+
+impl Circuit {
+    fn open(&mut self) {
+        self.state = CircuitState::Open;
+        self.opened_at = Instant::now();
+        self.outcomes.clear();
+        self.trial_in_progress = false;
+    }
+
+    fn close(&mut self) {
+        self.state = CircuitState::Closed;
+        self.outcomes.clear();
+        self.trial_in_progress = false;
+    }
+}
+
+static BREAKERS: LazyLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> = LazyLock::new(Default::default);
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Circuit breaker shared by the functions registered under its name
+pub struct CircuitBreaker {
+    name: String,
+    circuit: Mutex<Circuit>,
+}
+
+impl CircuitBreaker {
+    /// The breaker registered under `name`, registered with the default config on first use
+    pub fn named(name: &str) -> Arc<CircuitBreaker> {
+        let mut breakers = lock(&BREAKERS);
+        if let Some(breaker) = breakers.get(name) {
+            return breaker.clone();
+        }
+        breakers
+            .entry(name.to_owned())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(name, CircuitBreakerConfig::default())))
+            .clone()
+    }
+
+    /// Registers the breaker `name` with the config, a registered breaker is reconfigured and closed.
+    /// An invalid config is rejected and leaves the breaker unchanged
+    pub fn configure(name: &str, config: CircuitBreakerConfig) -> Result<Arc<CircuitBreaker>, CircuitBreakerConfigError> {
+        if !(config.failure_ratio > 0.0 && config.failure_ratio <= 1.0) {
+            return Err(CircuitBreakerConfigError::FailureRatio { name: name.to_owned(), failure_ratio: config.failure_ratio });
+        }
+        if !(0 < config.minimum_calls && config.minimum_calls <= config.window) {
+            return Err(CircuitBreakerConfigError::MinimumCalls {
+                name: name.to_owned(),
+                minimum_calls: config.minimum_calls,
+                window: config.window,
+            });
+        }
+        let breaker = CircuitBreaker::named(name);
+        let mut circuit = lock(&breaker.circuit);
+        circuit.config = config;
+        circuit.close();
+        drop(circuit);
+        Ok(breaker)
+    }
+
+    fn new(name: &str, config: CircuitBreakerConfig) -> CircuitBreaker {
+        let circuit = Circuit {
+            config,
+            state: CircuitState::Closed,
+            opened_at: Instant::now(),
+            outcomes: VecDeque::new(),
+            trial_in_progress: false,
+        };
+        CircuitBreaker { name: name.to_owned(), circuit: Mutex::new(circuit) }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Current state, an open breaker whose cool-down elapsed is reported half-open
+    pub fn state(&self) -> CircuitState {
+        let circuit = lock(&self.circuit);
+        match circuit.state {
+            CircuitState::Open if circuit.opened_at.elapsed() >= circuit.config.cool_down => CircuitState::HalfOpen,
+            state => state,
+        }
+    }
+
+    /// Closes the breaker and forgets the outcomes of previous calls
+    pub fn reset(&self) {
+        lock(&self.circuit).close();
+    }
+
+    /// Calls `operation` if the breaker lets the call through and records its outcome, every error is a failure
+    pub fn call_sync<T, E, Op>(&self, operation: Op) -> Result<T, E>
+    where
+        E: From<CircuitOpen>,
+        Op: FnOnce() -> Result<T, E>,
+    {
+        self.call_sync_if(operation, |_| true)
+    }
+
+    /// Calls `operation` if the breaker lets the call through and records its outcome, errors for which `is_failure` does not hold
+    /// are recorded as successful calls
+    pub fn call_sync_if<T, E, Op, P>(&self, operation: Op, is_failure: P) -> Result<T, E>
+    where
+        E: From<CircuitOpen>,
+        Op: FnOnce() -> Result<T, E>,
+        P: FnOnce(&E) -> bool,
+    {
+        let permit = self.permit().map_err(E::from)?;
+        let result = operation();
+        permit.record(!result.as_ref().is_err_and(is_failure));
+        result
+    }
+
+    /// Awaits `future` if the breaker lets the call through and records its outcome, every error is a failure
+    pub async fn call<T, E, F>(self: Arc<Self>, future: F) -> Result<T, E>
+    where
+        E: From<CircuitOpen>,
+        F: Future<Output = Result<T, E>>,
+    {
+        self.call_if(future, |_| true).await
+    }
+
+    /// Async version of [`call_sync_if`](CircuitBreaker::call_sync_if)
+    pub async fn call_if<T, E, F, P>(self: Arc<Self>, future: F, is_failure: P) -> Result<T, E>
+    where
+        E: From<CircuitOpen>,
+        F: Future<Output = Result<T, E>>,
+        P: FnOnce(&E) -> bool,
+    {
+        let permit = self.permit().map_err(E::from)?;
+        let result = future.await;
+        permit.record(!result.as_ref().is_err_and(is_failure));
+        result
+    }
+
+    fn permit(&self) -> Result<Permit<'_>, CircuitOpen> {
+        let mut circuit = lock(&self.circuit);
+        let open = |retry_after| Err(CircuitOpen { name: self.name.clone(), retry_after });
+        match circuit.state {
+            CircuitState::Closed => return Ok(Permit { breaker: self, trial: false }),
+            CircuitState::Open => {
+                let elapsed = circuit.opened_at.elapsed();
+                if elapsed < circuit.config.cool_down {
+                    return open(circuit.config.cool_down - elapsed);
+                }
+                circuit.state = CircuitState::HalfOpen;
+            }
+            CircuitState::HalfOpen if circuit.trial_in_progress => return open(Duration::ZERO),
+            CircuitState::HalfOpen => {}
+        }
+        circuit.trial_in_progress = true;
+        Ok(Permit { breaker: self, trial: true })
+    }
+}
+
+/// Call let through by a breaker. A trial call which is dropped before completing, e.g a cancelled future,
+/// lets the next call through as the trial
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    trial: bool,
+}
+
+impl Permit<'_> {
+    fn record(mut self, success: bool) {
+        let mut circuit = lock(&self.breaker.circuit);
+        let trial = std::mem::replace(&mut self.trial, false);
+        match circuit.state {
+            //only the trial call decides, other calls started before the breaker opened
+            CircuitState::HalfOpen if !trial => {}
+            CircuitState::HalfOpen if success => circuit.close(),
+            CircuitState::HalfOpen => circuit.open(),
+            CircuitState::Closed => {
+                circuit.outcomes.push_back(!success);
+                while circuit.outcomes.len() > circuit.config.window {
+                    circuit.outcomes.pop_front();
+                }
+                let calls = circuit.outcomes.len();
+                let failures = circuit.outcomes.iter().filter(|failed| **failed).count();
+                let threshold = circuit.config.failure_ratio * calls as f64;
+                if calls >= circuit.config.minimum_calls && failures > 0 && failures as f64 >= threshold {
+                    circuit.open();
+                }
+            }
+            //the call started before the breaker opened
+            CircuitState::Open => {}
+        }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.trial {
+            lock(&self.breaker.circuit).trial_in_progress = false;
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Closures as steps of a pipeline.
+//!
+//! A closure composed after a function receives the result of the function and must return a Result with the same error type,
+//! e.g `compose!(authenticate -> |auth_data| Ok(auth_data.0) -> generate_token -> with_args(request))`.
+//! The argument and error type of the closure are taken from the function composed before it, so they need not be annotated.
+//! Closures returning a future(`|a| async move { .. }` or async closures) are lifted as async functions.
+
+use std::future::Future;
+
+use paste::paste;
+
+use crate::*;
+
+/// Exposes the Ok and Err types of the Result returned by a function(or future returned by an async function)
+pub trait FnOutput {
+    type Output;
+    type Error;
+}
+
+macro_rules! impl_fn_output {
+    ([$($args:ident),*], $arg_size:literal) => {
+        paste!{
+            impl<'a, $($args,)* R, E> FnOutput for [<BoxedFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = R;
+                type Error = E;
+            }
+
+            impl<'a, $($args,)* R, E> FnOutput for [<BoxedAsyncFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = R;
+                type Error = E;
+            }
+
+            impl<'a, $($args,)* R, E> FnOutput for [<SharedFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = R;
+                type Error = E;
+            }
+
+            impl<'a, $($args,)* R, E> FnOutput for [<SharedAsyncFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = R;
+                type Error = E;
+            }
+        }
+    };
+}
+
+impl_fn_output!([T1], 1);
+impl_fn_output!([T1, T2], 2);
+impl_fn_output!([T1, T2, T3], 3);
+impl_fn_output!([T1, T2, T3, T4], 4);
+impl_fn_output!([T1, T2, T3, T4, T5], 5);
+impl_fn_output!([T1, T2, T3, T4, T5, T6], 6);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7], 7);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8], 8);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9], 9);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], 10);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], 11);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], 12);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], 13);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], 14);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15);
+impl_fn_output!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16);
+
+/// Lifts a sync closure composed after function `_left` into BoxedFn1
+pub fn lift_closure<'a, L, C, F>(_left: &L, f: F) -> BoxedFn1<'a, L::Output, C, L::Error>
+where
+    L: FnOutput,
+    F: FnOnce(L::Output) -> Result<C, L::Error> + Send + Sync + 'a,
+{
+    lift_sync_fn1(f)
+}
+
+/// Lifts a closure returning a future composed after function `_left` into BoxedAsyncFn1
+pub fn lift_async_closure<'a, L, C, F, Fut>(_left: &L, f: F) -> BoxedAsyncFn1<'a, L::Output, C, L::Error>
+where
+    L: FnOutput,
+    F: FnOnce(L::Output) -> Fut + Send + Sync + 'a,
+    Fut: Future<Output = Result<C, L::Error>> + Send + 'a,
+{
+    lift_async_fn1(move |b| Box::pin(f(b)))
+}
+
+/// Lifts a sync closure composed after function `_left` into SharedFn1
+pub fn lift_shared_closure<'a, L, C, F>(_left: &L, f: F) -> SharedFn1<'a, L::Output, C, L::Error>
+where
+    L: FnOutput,
+    F: Fn(L::Output) -> Result<C, L::Error> + Send + Sync + 'a,
+{
+    lift_shared_sync_fn1(f)
+}
+
+/// Lifts a closure returning a future composed after function `_left` into SharedAsyncFn1
+pub fn lift_shared_async_closure<'a, L, C, F, Fut>(_left: &L, f: F) -> SharedAsyncFn1<'a, L::Output, C, L::Error>
+where
+    L: FnOutput,
+    F: Fn(L::Output) -> Fut + Send + Sync + 'a,
+    Fut: Future<Output = Result<C, L::Error>> + Send + 'a,
+{
+    lift_shared_async_fn1(move |b| Box::pin(f(b)))
+}
+
+/// Wraps a sync closure composed after step `_left` in a [`SyncStep`]
+pub fn closure_step<L, C, F>(_left: &L, f: F) -> SyncStep<F, (L::Output,)>
+where
+    L: Step,
+    F: FnOnce(L::Output) -> Result<C, L::Error>,
+{
+    SyncStep::new(f)
+}
+
+/// Wraps a closure returning a future composed after step `_left` in an [`AsyncStep`]
+pub fn async_closure_step<L, C, F, Fut>(_left: &L, f: F) -> AsyncStep<F, (L::Output,)>
+where
+    L: Step,
+    F: FnOnce(L::Output) -> Fut,
+    Fut: Future<Output = Result<C, L::Error>>,
+{
+    AsyncStep::new(f)
+}
@@ -0,0 +1,158 @@
+//! Type keyed dependency injection container.
+//!
+//! Providers are registered by the type they provide. Arguments of a composeable function marked with
+//! `#[composeable(inject(arg))]` are resolved from the container when the pipeline built with
+//! `compose!(... -> with_container(&container) ...)` is called.
+//!
+//! Three kinds of providers are supported
+//! * singleton - a value which is cloned every time it is resolved
+//! * factory - a function called every time the value is resolved
+//! * async factory - an async function called every time the value is resolved, e.g getting a connection from a pool.
+//!   Values provided by async factories can only be injected into async functions.
+
+use std::any::{type_name, Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+type AnyValue = Box<dyn Any + Send>;
+
+#[derive(Clone)]
+enum Provider {
+    Singleton(Arc<dyn Fn() -> AnyValue + Send + Sync>),
+    Factory(Arc<dyn Fn() -> AnyValue + Send + Sync>),
+    AsyncFactory(Arc<dyn Fn() -> BoxFuture<'static, AnyValue> + Send + Sync>),
+}
+
+/// Error returned when a value cannot be resolved from the [`Container`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContainerError {
+    /// No provider is registered for the type
+    NotRegistered(&'static str),
+    /// The type is provided by an async factory but was resolved synchronously, e.g for a sync function
+    AsyncProvider(&'static str),
+    /// A function with injected arguments was composed without `with_container`
+    NoContainer,
+}
+
+impl Display for ContainerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContainerError::NotRegistered(type_name) => write!(f, "no provider registered for type {}", type_name),
+            ContainerError::AsyncProvider(type_name) => {
+                write!(f, "type {} is provided by an async factory and cannot be resolved synchronously", type_name)
+            }
+            ContainerError::NoContainer => {
+                write!(f, "function with injected arguments must be composed with with_container(&container)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContainerError {}
+
+thread_local! {
+    static CURRENT_CONTAINER: RefCell<Option<Container>> = const { RefCell::new(None) };
+}
+
+/// Registry of providers keyed by the type they provide.
+///
+/// Cloning the container is cheap, the providers are reference counted.
+#[derive(Clone, Default)]
+pub struct Container {
+    providers: HashMap<TypeId, Provider>,
+}
+
+impl Container {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a value which is cloned every time type T is resolved
+    pub fn register_singleton<T: Clone + Send + Sync + 'static>(&mut self, value: T) -> &mut Self {
+        let provider = Provider::Singleton(Arc::new(move || Box::new(value.clone()) as AnyValue));
+        self.providers.insert(TypeId::of::<T>(), provider);
+        self
+    }
+
+    /// Registers a factory which is called every time type T is resolved
+    pub fn register_factory<T, F>(&mut self, factory: F) -> &mut Self
+    where
+        T: Send + 'static,
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        let provider = Provider::Factory(Arc::new(move || Box::new(factory()) as AnyValue));
+        self.providers.insert(TypeId::of::<T>(), provider);
+        self
+    }
+
+    /// Registers an async factory which is called every time type T is resolved
+    pub fn register_async_factory<T, F, Fut>(&mut self, factory: F) -> &mut Self
+    where
+        T: Send + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        let provider = Provider::AsyncFactory(Arc::new(move || factory().map(|value| Box::new(value) as AnyValue).boxed()));
+        self.providers.insert(TypeId::of::<T>(), provider);
+        self
+    }
+
+    /// Returns true if a provider is registered for type T
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.providers.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Resolves a value of type T. Fails for types provided by async factories, use [`Container::resolve_async`] for them.
+    pub fn resolve<T: 'static>(&self) -> Result<T, ContainerError> {
+        match self.providers.get(&TypeId::of::<T>()) {
+            Some(Provider::Singleton(provider)) | Some(Provider::Factory(provider)) => Ok(downcast(provider())),
+            Some(Provider::AsyncFactory(_)) => Err(ContainerError::AsyncProvider(type_name::<T>())),
+            None => Err(ContainerError::NotRegistered(type_name::<T>())),
+        }
+    }
+
+    /// Resolves a value of type T from any kind of provider
+    pub async fn resolve_async<T: 'static>(&self) -> Result<T, ContainerError> {
+        let provider = self.providers.get(&TypeId::of::<T>()).cloned();
+        match provider {
+            Some(Provider::Singleton(provider)) | Some(Provider::Factory(provider)) => Ok(downcast(provider())),
+            Some(Provider::AsyncFactory(provider)) => Ok(downcast(provider().await)),
+            None => Err(ContainerError::NotRegistered(type_name::<T>())),
+        }
+    }
+
+    /// Makes this container the current container while f is running.
+    ///
+    /// Functions with injected arguments which are composed inside f capture the current container.
+    /// This is what `with_container` of the compose macros expands to. The previous container is restored even if f panics.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Restore(Option<Container>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT_CONTAINER.with(|current| current.replace(self.0.take()));
+            }
+        }
+
+        let _restore = Restore(CURRENT_CONTAINER.with(|current| current.replace(Some(self.clone()))));
+        f()
+    }
+
+    /// Returns the current container set by [`Container::scope`].
+    ///
+    /// Fails with [`ContainerError::NoContainer`] outside of [`Container::scope`], i.e when a function with injected arguments
+    /// is composed without with_container. The functions generated for it return the error from every call.
+    pub fn current() -> Result<Container, ContainerError> {
+        CURRENT_CONTAINER.with(|current| current.borrow().clone()).ok_or(ContainerError::NoContainer)
+    }
+}
+
+fn downcast<T: 'static>(value: AnyValue) -> T {
+    *value.downcast::<T>().expect("provider registered for a type returns value of the same type")
+}
@@ -0,0 +1,25 @@
+//! Result type of composeable functions.
+
+/// Splits the Result returned by a function into its Ok and Err types.
+///
+/// Used by the code generated for functions with injected arguments, and to check that a composeable function returns a Result.
+#[diagnostic::on_unimplemented(
+    message = "composeable function must return Result, e.g `-> Result<T, E>`, found `{Self}`",
+    label = "not a Result",
+    note = "type aliases of Result are accepted"
+)]
+pub trait FnResult {
+    type Ok;
+    type Err;
+
+    fn into_result(self) -> Result<Self::Ok, Self::Err>;
+}
+
+impl<T, E> FnResult for Result<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
@@ -0,0 +1,251 @@
+//! Injection of an argument at any position of a function.
+//!
+//! [`Injector`](crate::Injector) always binds the last argument of a function, [`InjectorAt`] binds the argument at position N (zero based).
+//! This allows composing functions whose dependencies are not the last argument, e.g functions from other crates.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use paste::paste;
+
+use crate::*;
+
+/// Injects the argument at position N of Self, returning a function(O) accepting the remaining arguments.
+///
+/// `f.provide_at::<0>(value)` in compose macros is same as calling `InjectorAt::<0, _, _>::provide_at(f, value)`
+pub trait InjectorAt<const N: usize, I, O> {
+    fn provide_at(self, a: I) -> O;
+}
+
+/// Inserts Item at position N of the tuple Rest to build Self. Used by [`ProvideAt`] to inject an argument of a step.
+pub trait InsertAt<const N: usize> {
+    type Rest;
+    type Item;
+
+    fn insert(rest: Self::Rest, item: Self::Item) -> Self;
+}
+
+macro_rules! impl_injector_at {
+    ($n:literal, [$($before:ident),*], $provided:ident, [$($after:ident),*], $arg_size:literal, $return_fn_arg_size:literal) => {
+        paste!{
+            #[doc = concat!("InjectorAt implementation injecting argument ", stringify!($n), " of a sync function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($before: 'a,)* $provided: Send + Sync + 'a, $($after: 'a,)* R: 'a, E: 'a> InjectorAt<$n, $provided, [<BoxedFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E>> for [<BoxedFn $arg_size>]<'a, $($before,)* $provided, $($after,)* R, E> {
+                fn provide_at(self, provided: $provided) -> [<BoxedFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E> {
+                    Box::new(move |$([<$before:lower>]: $before,)* $([<$after:lower>]: $after,)*| self($([<$before:lower>],)* provided, $([<$after:lower>],)*))
+                }
+            }
+
+            #[doc = concat!("InjectorAt implementation injecting argument ", stringify!($n), " of an async function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($before: 'a,)* $provided: Send + Sync + 'a, $($after: 'a,)* R: 'a, E: 'a> InjectorAt<$n, $provided, [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E>> for [<BoxedAsyncFn $arg_size>]<'a, $($before,)* $provided, $($after,)* R, E> {
+                fn provide_at(self, provided: $provided) -> [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E> {
+                    Box::new(move |$([<$before:lower>]: $before,)* $([<$after:lower>]: $after,)*| self($([<$before:lower>],)* provided, $([<$after:lower>],)*))
+                }
+            }
+
+            #[doc = concat!("InjectorAt implementation injecting argument ", stringify!($n), " of a shared sync function with ", stringify!($arg_size), " arguments. The provided value is cloned on every call")]
+            impl<'a, $($before: 'a,)* $provided: Clone + Send + Sync + 'a, $($after: 'a,)* R: 'a, E: 'a> InjectorAt<$n, $provided, [<SharedFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E>> for [<SharedFn $arg_size>]<'a, $($before,)* $provided, $($after,)* R, E> {
+                fn provide_at(self, provided: $provided) -> [<SharedFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E> {
+                    Arc::new(move |$([<$before:lower>]: $before,)* $([<$after:lower>]: $after,)*| self($([<$before:lower>],)* provided.clone(), $([<$after:lower>],)*))
+                }
+            }
+
+            #[doc = concat!("InjectorAt implementation injecting argument ", stringify!($n), " of a shared async function with ", stringify!($arg_size), " arguments. The provided value is cloned on every call")]
+            impl<'a, $($before: 'a,)* $provided: Clone + Send + Sync + 'a, $($after: 'a,)* R: 'a, E: 'a> InjectorAt<$n, $provided, [<SharedAsyncFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E>> for [<SharedAsyncFn $arg_size>]<'a, $($before,)* $provided, $($after,)* R, E> {
+                fn provide_at(self, provided: $provided) -> [<SharedAsyncFn $return_fn_arg_size>]<'a, $($before,)* $($after,)* R, E> {
+                    Arc::new(move |$([<$before:lower>]: $before,)* $([<$after:lower>]: $after,)*| self($([<$before:lower>],)* provided.clone(), $([<$after:lower>],)*))
+                }
+            }
+
+            impl<$($before,)* $provided, $($after,)*> InsertAt<$n> for ($($before,)* $provided, $($after,)*) {
+                type Rest = ($($before,)* $($after,)*);
+                type Item = $provided;
+
+                fn insert(rest: Self::Rest, item: Self::Item) -> Self {
+                    let ($([<$before:lower>],)* $([<$after:lower>],)*) = rest;
+                    ($([<$before:lower>],)* item, $([<$after:lower>],)*)
+                }
+            }
+        }
+    };
+}
+
+impl_injector_at!(0, [], T1, [T2], 2, 1);
+impl_injector_at!(1, [T1], T2, [], 2, 1);
+
+impl_injector_at!(0, [], T1, [T2, T3], 3, 2);
+impl_injector_at!(1, [T1], T2, [T3], 3, 2);
+impl_injector_at!(2, [T1, T2], T3, [], 3, 2);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4], 4, 3);
+impl_injector_at!(1, [T1], T2, [T3, T4], 4, 3);
+impl_injector_at!(2, [T1, T2], T3, [T4], 4, 3);
+impl_injector_at!(3, [T1, T2, T3], T4, [], 4, 3);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5], 5, 4);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5], 5, 4);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5], 5, 4);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5], 5, 4);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [], 5, 4);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6], 6, 5);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6], 6, 5);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6], 6, 5);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6], 6, 5);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6], 6, 5);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [], 6, 5);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7], 7, 6);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7], 7, 6);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7], 7, 6);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7], 7, 6);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7], 7, 6);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7], 7, 6);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [], 7, 6);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8], 8, 7);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8], 8, 7);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8], 8, 7);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8], 8, 7);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8], 8, 7);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8], 8, 7);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8], 8, 7);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [], 8, 7);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9], 9, 8);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9], 9, 8);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9], 9, 8);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9], 9, 8);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9], 9, 8);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9], 9, 8);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9], 9, 8);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9], 9, 8);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [], 9, 8);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9, T10], 10, 9);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9, T10], 10, 9);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9, T10], 10, 9);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9, T10], 10, 9);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9, T10], 10, 9);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9, T10], 10, 9);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9, T10], 10, 9);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9, T10], 10, 9);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [T10], 10, 9);
+impl_injector_at!(9, [T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, [], 10, 9);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], 11, 10);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9, T10, T11], 11, 10);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9, T10, T11], 11, 10);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9, T10, T11], 11, 10);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9, T10, T11], 11, 10);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9, T10, T11], 11, 10);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9, T10, T11], 11, 10);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9, T10, T11], 11, 10);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [T10, T11], 11, 10);
+impl_injector_at!(9, [T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, [T11], 11, 10);
+impl_injector_at!(10, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, [], 11, 10);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], 12, 11);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], 12, 11);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9, T10, T11, T12], 12, 11);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9, T10, T11, T12], 12, 11);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9, T10, T11, T12], 12, 11);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9, T10, T11, T12], 12, 11);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9, T10, T11, T12], 12, 11);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9, T10, T11, T12], 12, 11);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [T10, T11, T12], 12, 11);
+impl_injector_at!(9, [T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, [T11, T12], 12, 11);
+impl_injector_at!(10, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, [T12], 12, 11);
+impl_injector_at!(11, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, [], 12, 11);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9, T10, T11, T12, T13], 13, 12);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [T10, T11, T12, T13], 13, 12);
+impl_injector_at!(9, [T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, [T11, T12, T13], 13, 12);
+impl_injector_at!(10, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, [T12, T13], 13, 12);
+impl_injector_at!(11, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, [T13], 13, 12);
+impl_injector_at!(12, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, [], 13, 12);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9, T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [T10, T11, T12, T13, T14], 14, 13);
+impl_injector_at!(9, [T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, [T11, T12, T13, T14], 14, 13);
+impl_injector_at!(10, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, [T12, T13, T14], 14, 13);
+impl_injector_at!(11, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, [T13, T14], 14, 13);
+impl_injector_at!(12, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, [T14], 14, 13);
+impl_injector_at!(13, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, [], 14, 13);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9, T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [T10, T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(9, [T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, [T11, T12, T13, T14, T15], 15, 14);
+impl_injector_at!(10, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, [T12, T13, T14, T15], 15, 14);
+impl_injector_at!(11, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, [T13, T14, T15], 15, 14);
+impl_injector_at!(12, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, [T14, T15], 15, 14);
+impl_injector_at!(13, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, [T15], 15, 14);
+impl_injector_at!(14, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, [], 15, 14);
+
+impl_injector_at!(0, [], T1, [T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(1, [T1], T2, [T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(2, [T1, T2], T3, [T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(3, [T1, T2, T3], T4, [T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(4, [T1, T2, T3, T4], T5, [T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(5, [T1, T2, T3, T4, T5], T6, [T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(6, [T1, T2, T3, T4, T5, T6], T7, [T8, T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(7, [T1, T2, T3, T4, T5, T6, T7], T8, [T9, T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(8, [T1, T2, T3, T4, T5, T6, T7, T8], T9, [T10, T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(9, [T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, [T11, T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(10, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, [T12, T13, T14, T15, T16], 16, 15);
+impl_injector_at!(11, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, [T13, T14, T15, T16], 16, 15);
+impl_injector_at!(12, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, [T14, T15, T16], 16, 15);
+impl_injector_at!(13, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, [T15, T16], 16, 15);
+impl_injector_at!(14, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, [T16], 16, 15);
+impl_injector_at!(15, [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16, [], 16, 15);
+
+/// Step injecting the argument at position N of the underlying step, the statically typed counterpart of [`InjectorAt`]
+#[derive(Clone, Copy)]
+pub struct ProvideAt<S, P, const N: usize> {
+    step: S,
+    provided: P,
+}
+
+impl<S, const N: usize> ProvideAt<S, <S::Input as InsertAt<N>>::Item, N>
+where
+    S: Step,
+    S::Input: InsertAt<N>,
+{
+    pub fn new(step: S, provided: <S::Input as InsertAt<N>>::Item) -> Self {
+        ProvideAt { step, provided }
+    }
+}
+
+impl<S, P, const N: usize> Step for ProvideAt<S, P, N>
+where
+    S: Step,
+    S::Input: InsertAt<N, Item = P>,
+{
+    type Input = <S::Input as InsertAt<N>>::Rest;
+    type Output = S::Output;
+    type Error = S::Error;
+
+    fn call(self, input: Self::Input) -> impl Future<Output = Result<Self::Output, Self::Error>> {
+        self.step.call(InsertAt::insert(input, self.provided))
+    }
+}
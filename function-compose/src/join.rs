@@ -0,0 +1,164 @@
+//! Fan-out of a value to multiple functions which run concurrently.
+//!
+//! Each joined function receives a clone of the input. The functions are run using `futures::future::try_join`,
+//! so the first error is returned and the remaining functions are cancelled(dropped).
+//! The results of the functions are returned as a tuple, in the order the functions are joined.
+//!
+//! All the joined functions must have the same error type.
+
+use std::sync::Arc;
+
+use futures::FutureExt;
+
+use crate::{BoxedAsyncFn1, BoxedFn1, SharedAsyncFn1, SharedFn1, Step};
+
+/// Converts sync and async single argument boxed functions to BoxedAsyncFn1
+pub trait IntoBoxedAsyncFn1<'a, A> {
+    type Output;
+    type Error;
+
+    fn into_boxed_async_fn1(self) -> BoxedAsyncFn1<'a, A, Self::Output, Self::Error>;
+}
+
+impl<'a, A: Send + 'a, R: Send + 'a, E: Send + 'a> IntoBoxedAsyncFn1<'a, A> for BoxedFn1<'a, A, R, E> {
+    type Output = R;
+    type Error = E;
+
+    fn into_boxed_async_fn1(self) -> BoxedAsyncFn1<'a, A, R, E> {
+        Box::new(move |a: A| async move { self(a) }.boxed())
+    }
+}
+
+impl<'a, A, R, E> IntoBoxedAsyncFn1<'a, A> for BoxedAsyncFn1<'a, A, R, E> {
+    type Output = R;
+    type Error = E;
+
+    fn into_boxed_async_fn1(self) -> BoxedAsyncFn1<'a, A, R, E> {
+        self
+    }
+}
+
+/// Converts sync and async single argument shared functions to SharedAsyncFn1
+pub trait IntoSharedAsyncFn1<'a, A> {
+    type Output;
+    type Error;
+
+    fn into_shared_async_fn1(self) -> SharedAsyncFn1<'a, A, Self::Output, Self::Error>;
+}
+
+impl<'a, A: Send + 'a, R: Send + 'a, E: Send + 'a> IntoSharedAsyncFn1<'a, A> for SharedFn1<'a, A, R, E> {
+    type Output = R;
+    type Error = E;
+
+    fn into_shared_async_fn1(self) -> SharedAsyncFn1<'a, A, R, E> {
+        Arc::new(move |a: A| {
+            let f = self.clone();
+            async move { f(a) }.boxed()
+        })
+    }
+}
+
+impl<'a, A, R, E> IntoSharedAsyncFn1<'a, A> for SharedAsyncFn1<'a, A, R, E> {
+    type Output = R;
+    type Error = E;
+
+    fn into_shared_async_fn1(self) -> SharedAsyncFn1<'a, A, R, E> {
+        self
+    }
+}
+
+/// Joins a tuple of boxed functions into a single async function returning the tuple of their results
+pub trait Join<'a, A> {
+    type Output;
+    type Error;
+
+    fn join(self) -> BoxedAsyncFn1<'a, A, Self::Output, Self::Error>;
+}
+
+/// Joins a tuple of shared functions into a single shared async function returning the tuple of their results
+pub trait SharedJoin<'a, A> {
+    type Output;
+    type Error;
+
+    fn shared_join(self) -> SharedAsyncFn1<'a, A, Self::Output, Self::Error>;
+}
+
+/// Joins a tuple of sync or async boxed functions. See [`Join`]
+pub fn join<'a, A, J: Join<'a, A>>(fns: J) -> BoxedAsyncFn1<'a, A, J::Output, J::Error> {
+    fns.join()
+}
+
+/// Joins a tuple of sync or async shared functions. See [`SharedJoin`]
+pub fn shared_join<'a, A, J: SharedJoin<'a, A>>(fns: J) -> SharedAsyncFn1<'a, A, J::Output, J::Error> {
+    fns.shared_join()
+}
+
+/// Step running a tuple of steps concurrently with a clone of the same input, the statically typed counterpart of [`Join`]
+#[derive(Clone, Copy)]
+pub struct Joined<T> {
+    steps: T,
+}
+
+impl<T> Joined<T> {
+    pub fn new(steps: T) -> Self {
+        Joined { steps }
+    }
+}
+
+macro_rules! impl_join {
+    ($try_join:ident, $($f:ident),+) => {
+        paste::paste!{
+            #[doc = concat!("Join implementation for tuple of ", stringify!($($f),+), " boxed functions")]
+            impl<'a, A, E, $($f),+> Join<'a, A> for ($($f,)+)
+            where A: Clone + Send + 'a, E: Send + 'a, $($f: IntoBoxedAsyncFn1<'a, A, Error = E>, $f::Output: Send + 'a),+
+            {
+                type Output = ($($f::Output,)+);
+                type Error = E;
+
+                fn join(self) -> BoxedAsyncFn1<'a, A, Self::Output, E> {
+                    let ($([<$f:lower>],)+) = self;
+                    $(let [<$f:lower>] = [<$f:lower>].into_boxed_async_fn1();)+
+                    Box::new(move |a: A| {
+                        futures::future::$try_join($([<$f:lower>](a.clone())),+).boxed()
+                    })
+                }
+            }
+
+            #[doc = concat!("SharedJoin implementation for tuple of ", stringify!($($f),+), " shared functions")]
+            impl<'a, A, E, $($f),+> SharedJoin<'a, A> for ($($f,)+)
+            where A: Clone + Send + 'a, E: Send + 'a, $($f: IntoSharedAsyncFn1<'a, A, Error = E>, $f::Output: Send + 'a),+
+            {
+                type Output = ($($f::Output,)+);
+                type Error = E;
+
+                fn shared_join(self) -> SharedAsyncFn1<'a, A, Self::Output, E> {
+                    let ($([<$f:lower>],)+) = self;
+                    $(let [<$f:lower>] = [<$f:lower>].into_shared_async_fn1();)+
+                    Arc::new(move |a: A| {
+                        futures::future::$try_join($([<$f:lower>](a.clone())),+).boxed()
+                    })
+                }
+            }
+
+            #[doc = concat!("Step implementation for joining ", stringify!($($f),+), " steps")]
+            impl<A, E, $($f),+> Step for Joined<($($f,)+)>
+            where A: Clone, $($f: Step<Input = (A,), Error = E>),+
+            {
+                type Input = (A,);
+                type Output = ($($f::Output,)+);
+                type Error = E;
+
+                async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+                    let (a,) = input;
+                    let ($([<$f:lower>],)+) = self.steps;
+                    futures::future::$try_join($([<$f:lower>].call((a.clone(),))),+).await
+                }
+            }
+        }
+    };
+}
+
+impl_join!(try_join, F1, F2);
+impl_join!(try_join3, F1, F2, F3);
+impl_join!(try_join4, F1, F2, F3, F4);
+impl_join!(try_join5, F1, F2, F3, F4, F5);
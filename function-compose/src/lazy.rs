@@ -0,0 +1,212 @@
+//! Lazy injection of the last argument of a function.
+//!
+//! Unlike [`Injector`](crate::Injector), the injected value is produced by a provider function only when the function is called,
+//! i.e only if all the previous functions of the pipeline succeeded. The provider returns a Result and
+//! its error is converted to the error type of the function using From.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::FutureExt;
+use paste::paste;
+
+use crate::*;
+
+/// Injects the last argument of Self using a sync provider P which is called every time the function is called
+pub trait LazyInjector<P, O> {
+    fn provide_with(self, provider: P) -> O;
+}
+
+/// Injects the last argument of Self using an async provider P which is called every time the function is called.
+///
+/// The resulting function is always async.
+pub trait AsyncInjector<P, O> {
+    fn provide_async(self, provider: P) -> O;
+}
+
+macro_rules! impl_lazy_injector {
+    ([$($args:ident),*], $provided:ident, $arg_size:literal, $return_fn_arg_size:literal) => {
+        paste!{
+            #[doc = concat!("LazyInjector implementation for a sync function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: 'a,)* $provided: 'a, R: 'a, E: From<PE> + 'a, PE, P> LazyInjector<P, [<BoxedFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<BoxedFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: FnOnce() -> Result<$provided, PE> + Send + Sync + 'a
+            {
+                fn provide_with(self, provider: P) -> [<BoxedFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Box::new(move |$([<$args:lower>]: $args),*| self($([<$args:lower>],)* provider()?))
+                }
+            }
+
+            #[doc = concat!("LazyInjector implementation for an async function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: 'a,)* $provided: 'a, R: Send + 'a, E: From<PE> + Send + 'a, PE, P> LazyInjector<P, [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<BoxedAsyncFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: FnOnce() -> Result<$provided, PE> + Send + Sync + 'a
+            {
+                fn provide_with(self, provider: P) -> [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Box::new(move |$([<$args:lower>]: $args),*| match provider() {
+                        Ok(provided) => self($([<$args:lower>],)* provided),
+                        Err(error) => futures::future::ready(Err(E::from(error))).boxed(),
+                    })
+                }
+            }
+
+            #[doc = concat!("AsyncInjector implementation for a sync function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: Send + 'a,)* $provided: Send + 'a, R: Send + 'a, E: From<PE> + Send + 'a, PE, P, Fut> AsyncInjector<P, [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<BoxedFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: FnOnce() -> Fut + Send + Sync + 'a, Fut: Future<Output = Result<$provided, PE>> + Send + 'a
+            {
+                fn provide_async(self, provider: P) -> [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Box::new(move |$([<$args:lower>]: $args),*| {
+                        let provided = provider();
+                        async move {
+                            let provided = provided.await?;
+                            self($([<$args:lower>],)* provided)
+                        }.boxed()
+                    })
+                }
+            }
+
+            #[doc = concat!("AsyncInjector implementation for an async function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: Send + 'a,)* $provided: Send + 'a, R: Send + 'a, E: From<PE> + Send + 'a, PE, P, Fut> AsyncInjector<P, [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<BoxedAsyncFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: FnOnce() -> Fut + Send + Sync + 'a, Fut: Future<Output = Result<$provided, PE>> + Send + 'a
+            {
+                fn provide_async(self, provider: P) -> [<BoxedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Box::new(move |$([<$args:lower>]: $args),*| {
+                        let provided = provider();
+                        async move {
+                            let provided = provided.await?;
+                            self($([<$args:lower>],)* provided).await
+                        }.boxed()
+                    })
+                }
+            }
+
+            #[doc = concat!("LazyInjector implementation for a shared sync function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: 'a,)* $provided: 'a, R: 'a, E: From<PE> + 'a, PE, P> LazyInjector<P, [<SharedFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<SharedFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: Fn() -> Result<$provided, PE> + Send + Sync + 'a
+            {
+                fn provide_with(self, provider: P) -> [<SharedFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| self($([<$args:lower>],)* provider()?))
+                }
+            }
+
+            #[doc = concat!("LazyInjector implementation for a shared async function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: 'a,)* $provided: 'a, R: Send + 'a, E: From<PE> + Send + 'a, PE, P> LazyInjector<P, [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<SharedAsyncFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: Fn() -> Result<$provided, PE> + Send + Sync + 'a
+            {
+                fn provide_with(self, provider: P) -> [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| match provider() {
+                        Ok(provided) => self($([<$args:lower>],)* provided),
+                        Err(error) => futures::future::ready(Err(E::from(error))).boxed(),
+                    })
+                }
+            }
+
+            #[doc = concat!("AsyncInjector implementation for a shared sync function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: Send + 'a,)* $provided: Send + 'a, R: Send + 'a, E: From<PE> + Send + 'a, PE, P, Fut> AsyncInjector<P, [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<SharedFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: Fn() -> Fut + Send + Sync + 'a, Fut: Future<Output = Result<$provided, PE>> + Send + 'a
+            {
+                fn provide_async(self, provider: P) -> [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| {
+                        let f = self.clone();
+                        let provided = provider();
+                        async move {
+                            let provided = provided.await?;
+                            f($([<$args:lower>],)* provided)
+                        }.boxed()
+                    })
+                }
+            }
+
+            #[doc = concat!("AsyncInjector implementation for a shared async function with ", stringify!($arg_size), " arguments")]
+            impl<'a, $($args: Send + 'a,)* $provided: Send + 'a, R: Send + 'a, E: From<PE> + Send + 'a, PE, P, Fut> AsyncInjector<P, [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E>> for [<SharedAsyncFn $arg_size>]<'a, $($args,)* $provided, R, E>
+            where P: Fn() -> Fut + Send + Sync + 'a, Fut: Future<Output = Result<$provided, PE>> + Send + 'a
+            {
+                fn provide_async(self, provider: P) -> [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args,)* R, E> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| {
+                        let f = self.clone();
+                        let provided = provider();
+                        async move {
+                            let provided = provided.await?;
+                            f($([<$args:lower>],)* provided).await
+                        }.boxed()
+                    })
+                }
+            }
+        }
+    };
+}
+
+impl_lazy_injector!([T1], T2, 2, 1);
+impl_lazy_injector!([T1, T2], T3, 3, 2);
+impl_lazy_injector!([T1, T2, T3], T4, 4, 3);
+impl_lazy_injector!([T1, T2, T3, T4], T5, 5, 4);
+impl_lazy_injector!([T1, T2, T3, T4, T5], T6, 6, 5);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6], T7, 7, 6);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7], T8, 8, 7);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8], T9, 9, 8);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, 10, 9);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, 11, 10);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, 12, 11);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, 13, 12);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, 14, 13);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, 15, 14);
+impl_lazy_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16, 16, 15);
+
+/// Step injecting the last argument of the underlying step using a sync provider, the statically typed counterpart of [`LazyInjector`]
+#[derive(Clone, Copy)]
+pub struct ProvideWith<S, P> {
+    step: S,
+    provider: P,
+}
+
+impl<S, P> ProvideWith<S, P> {
+    pub fn new(step: S, provider: P) -> Self {
+        ProvideWith { step, provider }
+    }
+}
+
+impl<S, P, PE> Step for ProvideWith<S, P>
+where
+    S: Step,
+    S::Input: SplitLast,
+    S::Error: From<PE>,
+    P: FnOnce() -> Result<<S::Input as SplitLast>::Last, PE>,
+{
+    type Input = <S::Input as SplitLast>::Init;
+    type Output = S::Output;
+    type Error = S::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        let provided = (self.provider)()?;
+        self.step.call(SplitLast::join(input, provided)).await
+    }
+}
+
+/// Step injecting the last argument of the underlying step using an async provider, the statically typed counterpart of [`AsyncInjector`]
+#[derive(Clone, Copy)]
+pub struct ProvideAsync<S, P> {
+    step: S,
+    provider: P,
+}
+
+impl<S, P> ProvideAsync<S, P> {
+    pub fn new(step: S, provider: P) -> Self {
+        ProvideAsync { step, provider }
+    }
+}
+
+impl<S, P, Fut, PE> Step for ProvideAsync<S, P>
+where
+    S: Step,
+    S::Input: SplitLast,
+    S::Error: From<PE>,
+    P: FnOnce() -> Fut,
+    Fut: Future<Output = Result<<S::Input as SplitLast>::Last, PE>>,
+{
+    type Input = <S::Input as SplitLast>::Init;
+    type Output = S::Output;
+    type Error = S::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        let provided = (self.provider)().await?;
+        self.step.call(SplitLast::join(input, provided)).await
+    }
+}
@@ -33,9 +33,74 @@ trace_macros!(true);*/
 //! ```
 //! Argument 10(from with_args(10)). is passed to add_10 function and result of add_10 is passed to add_100
 //! 
+//! ## Functions with multiple arguments
+//! The first function of the pipeline can take more than one argument, they are all passed with with_args.
+//! Without with_args the composed function is returned instead of being called, it accepts the same arguments as the first function.
+//!
+//! ```ignore
+//! #[composeable()]
+//! pub fn multiply_add(factor: i32, a: i32, offset: i32) -> Result<i32, String> {
+//!     Ok(a * factor + offset)
+//! }
+//! let result = compose!(multiply_add -> add_10 -> with_args(2, 3, 4));
+//! assert_eq!(20, result.unwrap());
+//!
+//! let multiply_add_10 = compose!(multiply_add -> add_10);
+//! assert_eq!(20, multiply_add_10(2, 3, 4).unwrap());
+//! ```
+//!
+//! ## Functions in other modules
+//! Functions can be referenced by path, e.g. handlers::user::authenticate. The helpers generated by composeable are resolved
+//! relative to the module of the function, so the module does not need to be glob imported and functions with the same name in different modules can be composed.
+//! The helpers have the visibility of the function, a private function can be composed only where it can be called.
+//!
+//! ```ignore
+//! use crate::handlers::product_handler;
+//! let result = compose!(product_handler::find_product_by_ids.provide(&mut db_conn) -> product_handler::pack_product_data -> with_args(ids)).await?;
+//! ```
+//!
+//! ## Generic functions
+//! Composeable functions can have type parameters and where clauses. The type arguments are either inferred from the pipeline
+//! or given with turbofish syntax, which is needed when a type parameter is not used by the arguments.
+//!
+//! ```ignore
+//! #[composeable()]
+//! pub fn parse_number<T: FromStr>(text: String) -> Result<T, AppError> {
+//!     text.parse().map_err(|_| AppError::InvalidNumber(text))
+//! }
+//!
+//! #[composeable()]
+//! pub fn to_dto<T: Into<Dto>>(t: T) -> Result<Dto, AppError> {
+//!     Ok(t.into())
+//! }
+//!
+//! let result = compose!(parse_number::<Product> -> to_dto -> with_args(text));
+//! let result = compose!(find_product -> to_dto::<Product> -> with_args(id));
+//! ```
+//!
+//! ## Composing methods
+//! Inherent and trait methods can be marked composeable. The receiver(`self`, `&self` or `&mut self`) is treated as the first argument of the method.
+//! A method of a value is composed with `value.method`, the value is borrowed(or moved for `self` methods) by the composed function.
+//! The method can also be referenced by a qualified path, e.g `<RepositoryDB>::auth.provide_first(&mut repo)`, `<RepositoryDB as UserRepository>::auth` for a trait method
+//! or `Self::auth` inside the impl, which is the only form supported by compose_shared!. A path without `<..>` or `Self` always refers to a function.
+//! For trait methods the attribute is placed on the method declared in the trait.
+//!
+//! ```ignore
+//! #[async_trait]
+//! pub trait UserRepository {
+//!     #[composeable()]
+//!     async fn auth(&mut self, user_name: String, pass: String) -> Result<AuthData, FnError<ErrorType>>;
+//! }
+//!
+//! let mut repo = RepositoryDB::from(conn);
+//! let result = compose!(repo.auth.provide(pass) -> generate_token -> with_args(user_name)).await?;
+//! ```
+//!
 //! ## composing Async functions
 //! It is also possible to compose sync and asycn function.
-//! ##### <font color="#FFBF00"> __For async function,  return type should be BoxedFuture(futures crate)__</font>
+//! Async function can either be a plain `async fn`, a function returning `impl Future`
+//! or a function returning BoxFuture(futures crate) / `Pin<Box<dyn Future + Send>>`.
+//! Futures which are not already boxed are boxed by the composeable macro when composed with compose!.
 //! 
 //! ```ignore
 //! use function_compose::composeable;
@@ -47,6 +112,11 @@ trace_macros!(true);*/
 //!         Ok(r)
 //!     }.boxed()
 //! }
+//!
+//! #[composeable()]
+//! pub async fn add_native_async(a: i32, b: i32) -> Result<i32, String> {
+//!     Ok(a + b)
+//! }
 //! ```
 //! 
 //! ### Composing async and sync functions usage
@@ -94,17 +164,206 @@ trace_macros!(true);*/
 //! In the above example function add_3_arg_async, out of three arguments, 2 are injected during composing the function itself (using provide(100)) .
 //! This feature could be used for injecting connection pool or a repository instance(see the example project).
 //! 
+//! ## Injecting an argument at any position
+//! provide always injects the last argument of a function. provide_at::<N>(value) injects the argument at position N (zero based)
+//! and provide_first(value) is same as provide_at::<0>(value). This allows composing functions whose signatures cannot be changed
+//! to take the dependencies last. Positional injection is supported for functions with 2 to 16 arguments and can be mixed with provide.
+//!
+//! ```ignore
+//! #[composeable()]
+//! pub fn multiply_add(factor: i32, a: i32, offset: i32) -> Result<i32, String> {
+//!     Ok(a * factor + offset)
+//! }
+//! let result = compose!(add_10 -> multiply_add.provide_first(2).provide(5) -> with_args(1));
+//! assert_eq!(27, result.unwrap());
+//! ```
+//!
+//! ## Lazy providers
+//! provide_with(provider) and provide_async(provider) inject the last argument like provide, but the value is produced by the provider
+//! only when the function is called, i.e. when all the previous functions succeeded. The provider returns a Result and
+//! its error is converted to the error type of the function using From. provide_async always makes the composed function async.
+//! The providers produce owned values, use provide for injecting references.
+//!
+//! ```ignore
+//! #[composeable()]
+//! pub fn multiply_add(factor: i32, a: i32, offset: i32) -> Result<i32, FnError<String>> {
+//!     Ok(a * factor + offset)
+//! }
+//! fn load_offset() -> Result<i32, String> {
+//!     Ok(5)
+//! }
+//! //load_offset is called only if fail_if_odd succeeds
+//! let result = compose!(fail_if_odd -> multiply_add.provide_first(2).provide_with(load_offset) -> with_args(2));
+//! assert_eq!(9, result.unwrap());
+//!
+//! let result = compose!(add_10 -> multiply_add.provide_first(2).provide_async(|| async { Ok::<_, String>(5) }) -> with_args(1)).await;
+//! assert_eq!(27, result.unwrap());
+//! ```
+//!
+//! ## Injecting dependencies from a container
+//! Instead of passing every dependency with provide, dependencies can be registered by type in a Container.
+//! Arguments listed in inject(..) of the composeable attribute are resolved from the container every time the composed function is called,
+//! when the pipeline is composed with with_container(&container).
+//! * register_singleton(value) - the value is cloned every time it is resolved.
+//! * register_factory(f) - f is called every time the value is resolved.
+//! * register_async_factory(f) - async f is called every time the value is resolved, e.g. getting a pooled connection.
+//!   Values provided by async factories can only be injected into async functions.
+//!
+//! An injected argument of type `T`, `&T` or `&mut T` is resolved as `T`. The error type of the function must implement From&lt;ContainerError&gt;.
+//! Functions with injected arguments must be composed with with_container, otherwise every call fails with ContainerError::NoContainer.
+//!
+//! ```ignore
+//! #[composeable(inject(conn))]
+//! pub async fn find_user(id: i32, conn: &mut DbConnection) -> Result<User, AppError> {
+//!     ...
+//! }
+//! let mut container = Container::new();
+//! container.register_async_factory(move || get_pooled_connection(pool.clone()));
+//! let user = compose!(find_user -> to_user_dto -> with_container(&container) -> with_args(10)).await;
+//! ```
+//!
+//! ## Closures as steps
+//! A closure can be composed after any function to adapt its result without writing a composeable function.
+//! The closure receives the result of the previous function and returns a Result with the same error type, the types need not be annotated.
+//! Closures returning a future, i.e. `|a| async move { .. }` or async closures, make the composed function async.
+//!
+//! ```ignore
+//! let result = compose!(add_10 -> |a| Ok(a * 2) -> add_100 -> with_args(1));
+//! assert_eq!(122, result.unwrap());
+//!
+//! let result = compose!(authenticate.provide(&mut conn) -> |auth_data| async move { Ok(auth_data.0) } -> to_user_dto -> with_args(request)).await;
+//! ```
+//!
+//! ## Running functions concurrently
+//! join(f1, f2, ..) passes a clone of the previous function's result to each of the joined functions and runs them concurrently.
+//! The results are passed on as a tuple, so the next function must accept a tuple argument.
+//! The first error returned by any of the joined functions is returned and the remaining functions are cancelled.
+//! The joined functions must have the same error type. Up to 5 functions can be joined.
+//!
+//! ```ignore
+//! #[composeable()]
+//! pub fn sum_pair(pair: (i32, i32)) -> Result<i32, String> {
+//!     Ok(pair.0 + pair.1)
+//! }
+//! let result = compose!(add_10 -> join(add_100, add_100_async) -> sum_pair -> with_args(10)).await;
+//! assert_eq!(240, result.unwrap());
+//! ```
+//!
+//! ## Spreading tuple results
+//! A function returning a tuple can be connected to a function taking the tuple elements as separate arguments with `->>`.
+//! Functions with up to 16 arguments are supported.
+//!
+//! ```ignore
+//! #[composeable()]
+//! pub fn split_pair(a: i32) -> Result<(i32, String), FnError<String>> {
+//!     Ok((a, a.to_string()))
+//! }
+//!
+//! #[composeable()]
+//! pub fn join_pair(a: i32, b: String, separator: &str) -> Result<String, FnError<String>> {
+//!     Ok(format!("{}{}{}", a, separator, b))
+//! }
+//!
+//! let result = compose!(split_pair ->> join_pair.provide("-") -> with_args(1));
+//! assert_eq!("1-1", result.unwrap());
+//! ```
+//!
+//! ## Conditional branching
+//! branch(pred, then_fn, else_fn) calls the predicate with a reference to the previous function's result.
+//! If the predicate returns true the result is passed to then_fn, otherwise to else_fn.
+//! Both functions must have the same output and error type. If either of them is async, the composed function is async.
+//!
+//! ```ignore
+//! let result = compose!(add_10 -> branch(|a: &i32| *a > 15, add_100, add_10) -> with_args(10));
+//! assert_eq!(120, result.unwrap());
+//!
+//! let result = compose!(add_10 -> branch(|a| *a > 15, add_100_async, add_10) -> add_100 -> with_args(1)).await;
+//! assert_eq!(121, result.unwrap());
+//! ```
+//!
+//! ## Error handling in the pipeline
+//! Following stages can be used after any function. recover, map_err and timeout handle the error of the functions composed so far.
+//! * recover(handler) converts the error into an Ok value returned by the handler.
+//! * map_err(mapper) converts the error into another error type.
+//! * or_else(fallback_fn) calls the fallback function with a clone of the input of the function just before it when that function fails, e.g. read from the database when the value is not found in the cache.
+//!   The input must implement Clone. If either function is async, the composed function is async. or_else cannot follow recover, map_err or timeout.
+//! * timeout(duration) fails the async functions composed so far with `TimedOut` converted into their error type when they do not complete within the duration.
+//!   The error type must implement `From<TimedOut>`.
+//! 
+//! With compose! and compose_shared! these stages apply only to functions with a single remaining argument, provide the other arguments first.
+//! compose_static! supports any number of arguments.
+//!
+//! ```ignore
+//! let result = compose!(add_10 -> fail_if_odd -> recover(|_| 0) -> add_100 -> with_args(1));
+//! assert_eq!(100, result.unwrap());
+//!
+//! let user = compose!(create_user.provide(&mut db_conn) -> map_err(map_to_error_object()) -> with_args(payload)).await?;
+//!
+//! let product = compose!(find_in_cache -> or_else(find_in_db) -> with_args(product_id)).await;
+//! 
+//! // add_100 is called with 11, the input of fail_if_odd
+//! let result = compose!(add_10 -> fail_if_odd -> or_else(add_100) -> with_args(1));
+//! assert_eq!(111, result.unwrap());
+//!
+//! let auth_data = compose!(authenticate.provide(&mut conn) -> timeout(Duration::from_millis(500)) -> with_args(request)).await;
+//! ```
+//!
+//! ## Named pipelines
+//! pipeline! defines a named function from a chain of functions. The arguments of the function are passed to the first function of the chain.
+//! The defined function is composeable, so it can be used as a step of compose!, compose_shared! and compose_static! like any other composeable function.
+//! It must be declared async if any function in the chain is async. A composeable attribute, e.g. with retry, can be added to the definition, a bare `#[composeable]` is the same as none.
+//!
+//! ```ignore
+//! use function_compose::pipeline;
+//! pipeline! {
+//!     pub async fn auth_flow(auth_request: AuthRequest, conn: &mut DBConnection) -> Result<AuthResponse, FnError<ErrorType>> {
+//!         authenticate -> generate_token ->> pack_auth_result
+//!     }
+//! }
+//! let response = compose!(auth_flow.provide(&mut db_conn) -> with_args(auth_request)).await?;
+//! ```
+//!
+//! ## Reusable pipelines
+//! Functions composed with compose! are boxed FnOnce closures and can be called only once.
+//! compose_shared! builds the same pipeline from reference counted Fn closures (SharedFn1, SharedAsyncFn1 etc.),
+//! so it can be built once, cloned cheaply and called any number of times, even concurrently from many tasks.
+//! Values injected with provide must implement Clone as they are cloned on every call.
+//!
+//!```ignore
+//! use function_compose::compose_shared;
+//! let add_pipeline = compose_shared!(add_async.provide(100) -> add_10);
+//! let cloned = add_pipeline.clone();
+//! tokio::spawn(async move { cloned(10).await });
+//! assert_eq!(130, add_pipeline(20).await.unwrap());
+//!```
+//!
+//! ## Statically composed pipelines
+//! Every function composed by compose! or compose_shared! is boxed, and async functions box a future per step.
+//! compose_static! composes the functions into a statically typed Step instead, which is monomorphised by the compiler
+//! and does not allocate (apart from what the functions themselves allocate).
+//! The result of compose_static! is always a future, even when all the composed functions are sync.
+//! The boxed types are still useful when the pipeline has to be stored or chosen dynamically.
+//!
+//!```ignore
+//! use function_compose::{compose_static, Step};
+//! let result = compose_static!(add_10 -> add_async.provide(100) -> with_args(10)).await;
+//! assert_eq!(120, result.unwrap());
+//!
+//! let add_pipeline = compose_static!(add_10 -> add_async.provide(100));
+//! assert_eq!(130, add_pipeline.call((20,)).await.unwrap());
+//!```
+//!
 //! ## Retry in Fn Composer
 
 //!Composeable macro supports retrying a function at specified interval in case of Error returned by the function.
 //!This could be useful when trying make a database call or connect to network endpoint.
-//!Make sure to add https://docs.rs/retry/latest/retry/ to your project before proceeding with retry feature.
+//!The delay strategies are part of function-compose in `function_compose::retry`, no other dependency is needed.
 //!
 //!Retry mechanism is implemented as part of composeable procedureal macro.
 //!Below is example of  add_10  function configured to be retried 2 times after initial failure.
 //!
 //!```ignore
-//!use retry::delay::*;
+//!use function_compose::retry::*;
 //!#[composeable(retry = Fixed::from_millis(100).take(2))]
 //!pub fn add_10(a: i32) -> Result<i32, String> {
 //!    Ok(a + 10)
@@ -114,7 +373,7 @@ trace_macros!(true);*/
 
 //!Retry can be applied to both sync and async functions.
 //!
-//!for async functions, <font color="#FFBF00"> __all arguments to the function must be either shared reference or exclusive reference.__ </font>
+//!Owned arguments of a retried function are cloned for every attempt, <font color="#FFBF00"> __owned arguments must implement Clone, other arguments must be shared or exclusive references.__ </font>
 //!
 //!Below is example of  async function with retry.
 //!
@@ -133,12 +392,140 @@ trace_macros!(true);*/
 //!}
 //!```
 //!
-//!Apart from fixed duration retries, it is possible to configure with exponential delay.
-//!Refer to retry documentation for all available delay options https://docs.rs/retry/latest/retry/all.html
+//!Apart from fixed duration retries, it is possible to configure with `Exponential` or `Fibonacci` delays. Every strategy
+//!is an endless iterator of delays, `take(n)` limits the number of retries and `max_elapsed(duration)` stops retrying once
+//!the next retry would start after the duration. `map(jitter)` randomizes the delays, so that concurrent callers do not retry together.
+//!As in the `retry` crate, `Exponential::from_millis(10)` waits 10, 100, 1000 milliseconds, `factor(2.0)` doubles the delay instead.
+//!
+//!```ignore
+//!#[composeable(retry = Exponential::from_millis(10).map(jitter).max_elapsed(Duration::from_secs(5)))]
+//!pub fn add_10(a: i32) -> Result<i32, String> {
+//!    Ok(a + 10)
+//!}
+//!```
+//!
+//!By default every error is retried. `retry_if` takes a predicate(a function or closure taking a reference to the error and
+//!returning bool), only errors for which it returns true are retried and other errors are returned immediately.
+//!
+//!```ignore
+//!fn is_transient(error: &FnError<ErrorType>) -> bool {
+//!    matches!(error.underlying_error, Some(ErrorType::DBError(_)))
+//!}
+//!
+//!#[composeable(retry = Fixed::from_millis(100).take(3), retry_if = is_transient)]
+//!pub fn find_user(id: i32) -> Result<User, FnError<ErrorType>> {
+//!    ...
+//!}
+//!```
+//!
+//!`on_retry` takes a hook which is called before every retry with the number of the failed attempt(starting at 1), the delay
+//!before the next attempt and the error. By default the caller receives the error of the last attempt. With `report_exhausted`
+//!the error is converted from `RetryExhausted { attempts, elapsed, last_error }` when all attempts fail, which requires the
+//!error type of the function to implement `From<RetryExhausted<E>>`.
+//!
+//!```ignore
+//!impl From<RetryExhausted<FnError<ErrorType>>> for FnError<ErrorType> {
+//!    fn from(exhausted: RetryExhausted<FnError<ErrorType>>) -> Self {
+//!        FnError {
+//!            description: Some(format!("gave up after {} attempts in {:?}", exhausted.attempts, exhausted.elapsed)),
+//!            ..exhausted.last_error
+//!        }
+//!    }
+//!}
+//!
+//!fn log_retry(attempt: usize, delay: Duration, error: &FnError<ErrorType>) {
+//!    println!("attempt {attempt} failed with {error:?}, retrying in {delay:?}");
+//!}
+//!
+//!#[composeable(retry = Fixed::from_millis(100).take(3), retry_if = is_transient, on_retry = log_retry, report_exhausted)]
+//!pub fn find_user(id: i32) -> Result<User, FnError<ErrorType>> {
+//!    ...
+//!}
+//!```
+//!
+//!Delays of async functions are awaited on the timer selected by a cargo feature of function-compose: `tokio`(default),
+//!`async-std` or `smol`. Without any of them the delays are awaited on a timer thread. The `manual-clock` feature adds
+//!`runtime::manual_clock::ManualClock`, a clock advanced by the test. It replaces the timer of the whole process until it is dropped,
+//!so retries can be tested without waiting, also on a multi-thread runtime. Only one clock is installed at a time, the tests installing it run one after another.
+//!
+//!```toml
+//!function-compose = { version = "0.3.1", default-features = false, features = ["smol"] }
+//!```
+//!
+//!An async function can be limited in time with `timeout_ms`, a call which does not complete in time fails with `TimedOut`
+//!converted into the error type of the function, which must implement `From<TimedOut>`. With `retry`, `timeout_ms` limits
+//!every attempt and a timed out attempt is retried like any other error(subject to `retry_if`). `deadline_ms` limits all
+//!attempts including the delays, when it elapses the function fails with `TimedOut` without further retries.
+//!
+//!```ignore
+//!#[composeable(retry = Exponential::from_millis(10).take(5), timeout_ms = 500, deadline_ms = 2000)]
+//!pub async fn authenticate(request: AuthRequest, conn: &mut DBConnection) -> Result<AuthData, FnError<ErrorType>> {
+//!    ...
+//!}
+//!```
+//!
+//! ## Circuit breaker
+//!
+//!Retrying a failing dependency from every request makes an outage worse. `circuit_breaker = "name"` guards a function with the
+//!breaker registered under the name in function-compose, functions using the same name share the breaker. The breaker counts the
+//!outcome of every call(after all retries) and opens when the ratio of failed calls in the window reaches `failure_ratio`.
+//!The failures are the errors of a function without retry, and the errors of a retried function which exhausted the retries,
+//!i.e those `retry_if` holds for. `breaker_if = predicate` selects the failed calls by their error instead, other errors are
+//!counted as successful calls since the dependency answered. A call cut off by `deadline_ms` is not counted.
+//!While the breaker is open, the function and its retry loop are not called, the call fails with `CircuitOpen` converted into the
+//!error type of the function, which must implement `From<CircuitOpen>`. After `cool_down` the breaker is half-open and lets a single
+//!trial call through, which closes the breaker when it succeeds and opens it again when it fails.
+//!A breaker is created with `CircuitBreakerConfig::default()` on first use, `CircuitBreaker::configure` changes the thresholds.
+//!It returns a `CircuitBreakerConfigError` unless `failure_ratio` is above 0 and at most 1 and `minimum_calls` is between 1 and `window`.
+//!
+//!```ignore
+//!CircuitBreaker::configure("user_db", CircuitBreakerConfig {
+//!    failure_ratio: 0.5,
+//!    window: 20,
+//!    minimum_calls: 5,
+//!    cool_down: Duration::from_secs(30),
+//!})?;
+//!
+//!#[composeable(retry = Fixed::from_millis(100).take(2), circuit_breaker = "user_db")]
+//!pub async fn find_user(id: i32) -> Result<User, FnError<ErrorType>> {
+//!    ...
+//!}
+//!```
+
 
+use std::sync::Arc;
 
 use futures::{future::BoxFuture, FutureExt};
 
+mod step;
+pub use step::*;
+mod join;
+pub use join::*;
+mod branch;
+pub use branch::*;
+mod recover;
+pub use recover::*;
+mod inject_at;
+pub use inject_at::*;
+mod lazy;
+pub use lazy::*;
+mod spread;
+pub use spread::*;
+mod multi_arg;
+mod closure;
+pub use closure::*;
+mod container;
+pub use container::*;
+mod fn_result;
+pub use fn_result::*;
+pub mod retry;
+pub use retry::{RetryArgument, RetryError, RetryExhausted, clone_for_retry, retry_async, retry_sync};
+pub mod runtime;
+mod timeout;
+pub use timeout::*;
+mod circuit_breaker;
+pub use circuit_breaker::*;
+
 fn to_fn_error<E1, E2>(error:E1) -> E2 where E2:From<E1>{
     From::from(error)    
 }
@@ -268,6 +655,150 @@ macro_rules! impl_injector {
     };
 }
 
+macro_rules! shared_composer_generator {
+    ($arg1:ident, $return_type1:ident, $return_type2:ident, $error_type1:ident, $error_type2:ident) => {
+        paste!{
+            #[doc = concat!("Then implementation for composing shared sync function (SharedFn1) with another shared sync function(SharedFn1) ")]
+            impl<'a, $arg1: 'a + Send, $return_type1: 'a + Send, $return_type2: 'a, $error_type1: Send + 'a, $error_type2: Send + 'a>
+                Then<'a, $arg1, $return_type1, $return_type2, SharedFn1<'a, $return_type1, $return_type2, $error_type2>, SharedFn1<'a, $arg1, $return_type2, $error_type2>> for SharedFn1<'a, $arg1, $return_type1, $error_type1> where E2:From<E1>{
+
+                fn then(self, f: SharedFn1<'a, $return_type1, $return_type2, $error_type2>) -> SharedFn1<'a, $arg1, $return_type2, $error_type2> {
+                    let r1 = move |x: $arg1| {
+                        let g_result = self(x);
+                        match g_result{
+                                Ok(inner_result) => f(inner_result),
+                                Err(error) =>   Err(to_fn_error(error)),
+                            }
+                    };
+                    Arc::new(r1)
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing shared sync function(SharedFn1) with another shared async function(SharedAsyncFn1) ")]
+            impl<'a, $arg1: 'a + Send, $return_type1: 'a + Send, $return_type2: 'a, $error_type1: Send + 'a, $error_type2: Send + 'a>
+                Then<'a, $arg1, $return_type1, $return_type2, SharedAsyncFn1<'a, $return_type1, $return_type2, $error_type2>, SharedAsyncFn1<'a, $arg1, $return_type2, $error_type2>> for SharedFn1<'a, $arg1, $return_type1, $error_type1> where E2:From<E1>{
+
+                fn then(self, f: SharedAsyncFn1<'a, $return_type1, $return_type2, $error_type2>) -> SharedAsyncFn1<'a, $arg1, $return_type2, $error_type2> {
+                    let r1 = move |x: $arg1| {
+                        let g = self.clone();
+                        let f = f.clone();
+                        async move{
+                            let g_result = g(x);
+                            match g_result{
+                                Ok(inner_result) => f(inner_result).await,
+                                Err(error) =>   Err(to_fn_error(error)),
+                            }
+                        }.boxed()
+                    };
+                    Arc::new(r1)
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing shared async function(SharedAsyncFn1) with another shared sync function(SharedFn1) ")]
+            impl<'a, $arg1: 'a + Send, $return_type1: 'a + Send, $return_type2: 'a, $error_type1:Send +  'a, $error_type2:Send +  'a>
+                Then<'a, $arg1, $return_type1, $return_type2, SharedFn1<'a, $return_type1, $return_type2, $error_type2>, SharedAsyncFn1<'a, $arg1, $return_type2, $error_type2>> for SharedAsyncFn1<'a, $arg1, $return_type1, $error_type1> where E2:From<E1>{
+
+                fn then(self, f: SharedFn1<'a, $return_type1, $return_type2, $error_type2>) -> SharedAsyncFn1<'a, $arg1, $return_type2, $error_type2> {
+                    let r1 = move |a: $arg1| {
+                        let g = self.clone();
+                        let f = f.clone();
+                        async move {
+                            let g_result = g(a).await;
+                            match g_result{
+                                Ok(inner_result) => f(inner_result),
+                                Err(error) =>   Err(to_fn_error(error)),
+                            }
+                        }.boxed()
+                    };
+                    let r: SharedAsyncFn1<'a,$arg1, $return_type2, $error_type2> = Arc::new(r1);
+                    r
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing shared async function(SharedAsyncFn1) with another shared async function(SharedAsyncFn1) ")]
+            impl<'a, $arg1: 'a + Send, $return_type1: 'a + Send, $return_type2: 'a, $error_type1:Send +  'a, $error_type2:Send + 'a>
+                Then<'a, $arg1, $return_type1, $return_type2, SharedAsyncFn1<'a, $return_type1, $return_type2, $error_type2>, SharedAsyncFn1<'a, $arg1, $return_type2, $error_type2>> for SharedAsyncFn1<'a, $arg1, $return_type1, $error_type1> where E2:From<E1>{
+
+                fn then(self, f: SharedAsyncFn1<'a, $return_type1, $return_type2, $error_type2>) -> SharedAsyncFn1<'a, $arg1, $return_type2, $error_type2> {
+                    let r1 = move |a: $arg1| {
+                        let g = self.clone();
+                        let f = f.clone();
+                        async move {
+                            let g_result = g(a).await;
+                            match g_result{
+                                Ok(inner_result) => f(inner_result).await,
+                                Err(error) =>   Err(to_fn_error(error)),
+                            }
+                        }.boxed()
+                    };
+                    let r: SharedAsyncFn1<'a,$arg1, $return_type2, $error_type2> = Arc::new(r1);
+                    r
+                }
+            }
+        }
+    }
+}
+
+macro_rules! impl_shared_injector {
+    ([$($args:ident),*], $provided:ident, $return_type:ident, $error_type:ident, $arg_size:literal, $return_fn_arg_size:literal) => {
+
+        paste!  {
+            #[doc = concat!("dependency injection function shared_provider_f", stringify!($arg_size), " for injecting the last argument of a given shared sync function. The provided value is cloned on every call")]
+            pub fn [<shared_provider_f $arg_size>]<'a, $($args),*, $provided, $return_type, $error_type>(fn1: [<SharedFn $arg_size>]<'a, $($args),*, $provided, $return_type, $error_type>,provided_data: $provided,) -> [<SharedFn $return_fn_arg_size>]<'a, $($args),* , $return_type, $error_type> where $( $args: 'a ),*, $provided: Clone + Send + Sync + 'a, $return_type: 'a, $error_type: 'a{
+                    Arc::new(move |$( [<$args:lower>]:$args ),*| fn1($( [<$args:lower>]),*,  provided_data.clone()))
+            }
+
+            #[doc = concat!("dependency injection function shared_provider_async_f", stringify!($arg_size), " for injecting the last argument of a given shared async function. The provided value is cloned on every call")]
+            pub fn [<shared_provider_async_f $arg_size>]<'a, $($args),*, $provided, $return_type, $error_type>(fn1: [<SharedAsyncFn $arg_size>]<'a, $($args),*, $provided, $return_type, $error_type>,provided_data: $provided,) -> [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args),* , $return_type, $error_type> where $( $args: 'a ),*, $provided: Clone + Send + Sync + 'a, $return_type: 'a, $error_type: 'a{
+                    Arc::new(move |$( [<$args:lower>]:$args ),*| fn1($( [<$args:lower>]),*,  provided_data.clone()))
+            }
+
+        }
+        paste!{
+
+            #[doc = concat!("Injector implementation for a given shared sync function that accepts " , stringify!($return_fn_arg_size+1), " arguments and returns a shared function with ", stringify!($return_fn_arg_size), " arguments")]
+            impl<'a, $($args),*, $provided, $return_type, $error_type> Injector<$provided, [<SharedFn $return_fn_arg_size>]<'a, $($args),*, $return_type, $error_type>> for [<SharedFn $arg_size>] <'a, $($args),*, $provided, $return_type, $error_type>
+            where $( $args: 'a ),*, $provided: Clone + Send + Sync +'a, $return_type: 'a, $error_type: 'a
+            {
+                fn provide(self, a: $provided) -> [<SharedFn $return_fn_arg_size>]<'a, $($args),*, $return_type, $error_type> {
+                    [<shared_provider_f $arg_size>](self, a)
+                }
+            }
+
+            #[doc = concat!("Injector implementation for a given shared async function that accepts " , stringify!($return_fn_arg_size+1), " arguments  and returns a shared function with ", stringify!($return_fn_arg_size), " arguments")]
+            impl<'a, $($args),*, $provided, $return_type, $error_type> Injector<$provided, [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args),*, $return_type, $error_type>> for [<SharedAsyncFn $arg_size>] <'a, $($args),*, $provided, $return_type, $error_type>
+            where $( $args: 'a ),*, $provided: Clone + Send + Sync +'a, $return_type: 'a, $error_type: 'a
+            {
+                fn provide(self, a: $provided) -> [<SharedAsyncFn $return_fn_arg_size>]<'a, $($args),*, $return_type, $error_type> {
+                    [<shared_provider_async_f $arg_size>](self, a)
+                }
+            }
+        }
+    };
+}
+
+macro_rules! generate_shared_fn {
+    ( [$($args:ident),*], $return_type:ident,$error_type:ident, $arg_size:expr ) => {
+            paste!{
+                #[doc = concat!("Type alias  SharedFn", stringify!($arg_size), "  for reference counted Fn sync function with ", stringify!($arg_size), " arguments. Unlike BoxedFn", stringify!($arg_size), " it can be cloned and called any number of times")]
+                pub type [<SharedFn $arg_size>]<'a, $($args),*, $return_type, $error_type> = Arc<dyn Fn($($args),*) -> Result<$return_type, $error_type> + Send + Sync + 'a>;
+
+                #[doc = concat!("Type alias  SharedAsyncFn", stringify!($arg_size), "  for reference counted Fn async function with ", stringify!($arg_size), " arguments. Unlike BoxedAsyncFn", stringify!($arg_size), " it can be cloned and called any number of times")]
+                pub type [<SharedAsyncFn $arg_size>]<'a, $($args),*, $return_type,$error_type> = Arc<dyn Fn($($args),*) -> BoxFuture<'a, Result<$return_type, $error_type>> + Send + Sync + 'a>;
+
+                #[doc = concat!("Function to wrap Fn sync function with ", stringify!($arg_size), " aguments in an Arc and coerce it to SharedFn",stringify!($arg_size))]
+                pub fn [<lift_shared_sync_fn $arg_size>]<'a, $($args),*, $return_type, $error_type, F: Fn($($args),*) -> Result<$return_type, $error_type> + Send + Sync + 'a>(f: F,) -> [<SharedFn $arg_size>]<'a, $($args),*, $return_type, $error_type> {
+                    Arc::new(f)
+                }
+
+                #[doc = concat!("Function to wrap Fn async function with ", stringify!($arg_size), " aguments in an Arc and coerce it to SharedAsyncFn",stringify!($arg_size))]
+                pub fn [<lift_shared_async_fn $arg_size>]<'a, $($args),*, $return_type, $error_type, F: Fn($($args),*) -> BoxFuture<'a,Result<$return_type, $error_type>> + Send + Sync + 'a>(f: F,) -> [<SharedAsyncFn $arg_size>]<'a, $($args),*, $return_type, $error_type> {
+                    Arc::new(f)
+                }
+            }
+    }
+}
+
 macro_rules! generate_boxed_fn {
     ( [$($args:ident),*], $return_type:ident,$error_type:ident, $arg_size:expr ) => {
 
@@ -319,6 +850,77 @@ impl_injector!([T1, T2, T3, T4, T5, T6], T7, T8,E1, 7, 6);
 generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8], T9, E1, 8);
 impl_injector!([T1, T2, T3, T4, T5, T6, T7], T8, T9, E1, 8, 7);
 
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, E1, 9);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8], T9, T10, E1, 9, 8);
+
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, E1, 10);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, T11, E1, 10, 9);
+
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, E1, 11);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, T12, E1, 11, 10);
+
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, E1, 12);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, T13, E1, 12, 11);
+
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, E1, 13);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, T14, E1, 13, 12);
+
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, E1, 14);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, T15, E1, 14, 13);
+
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16, E1, 15);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, T16, E1, 15, 14);
+
+generate_boxed_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], T17, E1, 16);
+impl_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16, T17, E1, 16, 15);
+
+generate_shared_fn!([T1], T2, E1, 1);
+
+generate_shared_fn!([T1, T2], T3, E1, 2);
+impl_shared_injector!([T1], T2, T3, E1, 2, 1);
+
+generate_shared_fn!([T1, T2, T3], T4, E1, 3);
+impl_shared_injector!([T1, T2], T3, T4, E1, 3, 2);
+
+generate_shared_fn!([T1, T2, T3, T4], T5, E1, 4);
+impl_shared_injector!([T1, T2, T3], T4, T5, E1, 4, 3);
+
+generate_shared_fn!([T1, T2, T3, T4, T5], T6, E1, 5);
+impl_shared_injector!([T1, T2, T3, T4], T5, T6, E1, 5, 4);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6], T7, E1, 6);
+impl_shared_injector!([T1, T2, T3, T4, T5], T6, T7, E1, 6, 5);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7], T8, E1, 7);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6], T7, T8, E1, 7, 6);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8], T9, E1, 8);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7], T8, T9, E1, 8, 7);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, E1, 9);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8], T9, T10, E1, 9, 8);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, E1, 10);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, T11, E1, 10, 9);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, E1, 11);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, T12, E1, 11, 10);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, E1, 12);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, T13, E1, 12, 11);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, E1, 13);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, T14, E1, 13, 12);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, E1, 14);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, T15, E1, 14, 13);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16, E1, 15);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, T16, E1, 15, 14);
+
+generate_shared_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], T17, E1, 16);
+impl_shared_injector!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16, T17, E1, 16, 15);
+
 //Generates a function composition for BoxedFn1 as below. The below is example of composing sync with sync function.
 //Similar code is generated for composing sync with async function, async with sync function and async with async function.
 // impl<'a, T1: 'a + Send, T2: 'a + Send, T3: 'a>
@@ -334,10 +936,25 @@ impl_injector!([T1, T2, T3, T4, T5, T6, T7], T8, T9, E1, 8, 7);
 // }
 composer_generator!(T1, T2, T3, E1, E2);
 
+//Same as above but for the reference counted SharedFn1/SharedAsyncFn1 types which can be called more than once.
+shared_composer_generator!(T1, T2, T3, E1, E2);
+
 
 
 
 
+///Implemented by the marker type `fn_composer__<function name>` which `composeable` generates for a function.
+///
+///The marker groups the helpers used by the compose macros(the lifted and retry functions of the function) as associated
+///functions and has the visibility of the function.
+pub trait Composeable {
+    /// true if the function returns a future
+    const ASYNC: bool;
+
+    /// true if the function is composed with a retry strategy
+    const RETRYABLE: bool;
+}
+
 pub trait Injector<I, O> {
     fn provide(self, a: I) -> O;
 }
@@ -366,134 +983,531 @@ pub trait Then<'a, A, B, C, F, R> {
 #[macro_use]
 pub mod macros {
 
-
+    /// Composes the functions into a boxed `BoxedFn*`/`BoxedAsyncFn*` function, see the crate documentation for the supported stages.
+    ///
+    /// With `with_args` the composed function is called, and the result must be awaited if any of the functions is async.
     #[macro_export]
     macro_rules! compose {
-        ($fnLeft:ident,$is_left_fn_async:ident,-> with_args($args:expr) $($others:tt)*) => {
+        ($($tokens:tt)*) => {
+            $crate::compose_pipeline!(boxed; @with_container [] $($tokens)*)
+        };
+    }
+
+
+    /// Same as [`compose!`] but composes the reference counted `SharedFn*`/`SharedAsyncFn*` variants of the functions.
+    ///
+    /// Without `with_args` the composed function is returned, which can be cloned and called any number of times,
+    /// e.g. stored in application state and invoked from many tasks.
+    /// Values injected with `provide` must implement `Clone`, they are cloned on every call.
+    #[macro_export]
+    macro_rules! compose_shared {
+        ($($tokens:tt)*) => {
+            $crate::compose_pipeline!(shared; @with_container [] $($tokens)*)
+        };
+    }
+
+
+    /// Same as [`compose!`] but composes the functions into a statically typed [`Step`](crate::Step) (see [`Composed`](crate::Composed)).
+    ///
+    /// No boxing is involved, the resulting pipeline is monomorphised by the compiler.
+    /// With `with_args` the pipeline is called and the returned future must be awaited, even if all the functions are sync.
+    /// Without `with_args` the pipeline is returned and it can be invoked using [`Step::call`](crate::Step::call) with a tuple of arguments.
+    ///
+    /// This is a separate macro because the pipeline is a `Step` and not a boxed function, so it can't replace the result of [`compose!`].
+    /// The future of a pipeline is Send when all its functions and provided values are Send, so it can be spawned or awaited in an axum handler.
+    /// Generic code bounded only by `S: Step` can't rely on that, the trait does not require the future to be Send.
+    #[macro_export]
+    macro_rules! compose_static {
+        ($($tokens:tt)*) => {
+            $crate::compose_pipeline!(step; @with_container [] $($tokens)*)
+        };
+    }
+
+
+    /// Parser shared by [`compose!`], [`compose_shared!`] and [`compose_static!`].
+    ///
+    /// The first token is the flavour of the composed functions: `boxed`, `shared` or `step`.
+    /// Every stage is built by [`compose_stage!`] for that flavour. The function composed last is kept apart from
+    /// the functions composed before it until the next stage, so that `provide` and `or_else` apply to it alone.
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! compose_pipeline {
+        ($fl:ident; @with_container [$($acc:tt)*] -> with_container($container:expr) $($rest:tt)*) => {
+            $crate::Container::scope($container, || $crate::compose_pipeline!($fl; @start $($acc)* $($rest)*))
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] -> | $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_args [$($acc)*] sync [|] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] -> move | $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_args [$($acc)*] sync [move |] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] -> async | $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_args [$($acc)*] async [async |] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] -> async move | $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_args [$($acc)*] async [async move |] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] $obj:ident . $method:ident ( $($args:tt)* ) $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* $obj . $method ( $($args)* )] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] $obj:ident . $method:ident :: $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* $obj . $method ::] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] $obj:ident . $method:ident $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* @method($obj . $method)] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] provide_at :: < $n:literal > $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* provide_at :: < $n >] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] < $ty:ty > :: $method:ident $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* (<$ty>::$method)] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] < $ty:ty as $trait_path:path > :: $method:ident $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* (<$ty as $trait_path>::$method)] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] $fn:ident :: < $($ty:ty),+ > $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* ($fn ::<$($ty),+>)] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] $seg:ident :: $next:ident $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_path [$($acc)*] [$seg :: $next] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*] $token:tt $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* $token] $($rest)*)
+        };
+
+        ($fl:ident; @with_path [$($acc:tt)*] [$($path:tt)*] :: < $($ty:ty),+ > $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* ($($path)* ::<$($ty),+>)] $($rest)*)
+        };
+
+        ($fl:ident; @with_path [$($acc:tt)*] [$($path:tt)*] :: $seg:ident $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_path [$($acc)*] [$($path)* :: $seg] $($rest)*)
+        };
+
+        ($fl:ident; @with_path [$($acc:tt)*] [$($path:tt)*] $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* ($($path)*)] $($rest)*)
+        };
+
+        ($fl:ident; @closure_args [$($acc:tt)*] $kind:ident [$($closure:tt)*] | $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_body [$($acc)*] $kind [$($closure)* |] $($rest)*)
+        };
+
+        ($fl:ident; @closure_args [$($acc:tt)*] $kind:ident [$($closure:tt)*] $token:tt $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_args [$($acc)*] $kind [$($closure)* $token] $($rest)*)
+        };
+
+        ($fl:ident; @closure_body [$($acc:tt)*] $kind:ident [$($closure:tt)*] -> $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_return [$($acc)*] $kind [$($closure)* ->] $($rest)*)
+        };
+
+        ($fl:ident; @closure_body [$($acc:tt)*] $kind:ident [$($closure:tt)*] async $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_expr [$($acc)*] async [$($closure)* async] $($rest)*)
+        };
+
+        ($fl:ident; @closure_body [$($acc:tt)*] $kind:ident [$($closure:tt)*] $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_expr [$($acc)*] $kind [$($closure)*] $($rest)*)
+        };
+
+        ($fl:ident; @closure_return [$($acc:tt)*] $kind:ident [$($closure:tt)*] { $($block:tt)* } $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_end [$($acc)*] $kind [$($closure)* { $($block)* }] $($rest)*)
+        };
+
+        ($fl:ident; @closure_return [$($acc:tt)*] $kind:ident [$($closure:tt)*] $token:tt $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_return [$($acc)*] $kind [$($closure)* $token] $($rest)*)
+        };
+
+        ($fl:ident; @closure_expr [$($acc:tt)*] $kind:ident [$($closure:tt)*] -> $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_end [$($acc)*] $kind [$($closure)*] -> $($rest)*)
+        };
+
+        ($fl:ident; @closure_expr [$($acc:tt)*] $kind:ident [$($closure:tt)*] $token:tt $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @closure_expr [$($acc)*] $kind [$($closure)* $token] $($rest)*)
+        };
+
+        ($fl:ident; @closure_expr [$($acc:tt)*] $kind:ident [$($closure:tt)*]) => {
+            $crate::compose_pipeline!($fl; @closure_end [$($acc)*] $kind [$($closure)*])
+        };
+
+        ($fl:ident; @closure_end [$($acc:tt)*] sync [$($closure:tt)*] $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* -> @sync_closure($($closure)*)] $($rest)*)
+        };
+
+        ($fl:ident; @closure_end [$($acc:tt)*] async [$($closure:tt)*] $($rest:tt)*) => {
+            $crate::compose_pipeline!($fl; @with_container [$($acc)* -> @async_closure($($closure)*)] $($rest)*)
+        };
+
+        ($fl:ident; @with_container [$($acc:tt)*]) => {
+            $crate::compose_pipeline!($fl; @start $($acc)*)
+        };
+
+        ($fl:ident; @start branch($pred:expr, $then_fn:ident $(:: $then_path:ident)* $(:: < $($then_ty:ty),+ >)?, $else_fn:ident $(:: $else_path:ident)* $(:: < $($else_ty:ty),+ >)?) $($others:tt)*) => {
+            {
+                let f = $crate::compose_stage!($fl, branch, $pred,
+                    $crate::compose_stage!($fl, lift, ($then_fn $(:: $then_path)* $(::<$($then_ty),+>)?)),
+                    $crate::compose_stage!($fl, lift, ($else_fn $(:: $else_path)* $(::<$($else_ty),+>)?)));
+                $crate::compose_pipeline!($fl; f, $($others)*)
+            }
+        };
+
+        ($fl:ident; @start join($($fn:ident $(:: $fn_path:ident)* $(:: < $($fn_ty:ty),+ >)?),+) $($others:tt)*) => {
             {
-            let r = $fnLeft($args);
-            r
+                let f = $crate::compose_stage!($fl, join, ($( $crate::compose_stage!($fl, lift, ($fn $(:: $fn_path)* $(::<$($fn_ty),+>)?)), )+));
+                $crate::compose_pipeline!($fl; f, $($others)*)
             }
         };
 
-        ($fnLeft:ident,$is_left_fn_async:ident,.provide($p1:expr) $($others:tt)*) => {
+        ($fl:ident; @start @method($obj:ident . $method:ident) $($others:tt)*) => {
             {
-                use function_compose::Injector;
-                let p = $fnLeft.provide($p1);
-                let p1 = compose!(p,$is_left_fn_async,$($others)*);
-                p1
+                let f = $crate::compose_stage!($fl, method, $obj, $method);
+                $crate::compose_pipeline!($fl; f, $($others)*)
             }
         };
 
-        ($f_left:ident,$is_left_fn_async:ident,$f_right:ident, $isRightAsync:ident,  .provide($p:expr) $($others:tt)*) =>{
+        ($fl:ident; @start $fn:tt $($others:tt)*) => {
             {
-                let f_right = $f_right.provide($p);
-                let f3 = compose!($f_left,$is_left_fn_async,f_right,$isRightAsync,$($others)*);
-                f3
+                let f = $crate::compose_stage!($fl, lift, $fn);
+                $crate::compose_pipeline!($fl; f, $($others)*)
             }
         };
 
-        ($f_left:ident,$is_left_fn_async:ident,$f_right:ident, $isRightAsync:ident,  ->  $($others:tt)*) =>{
+        ($fl:ident; $f_left:ident, -> with_args($($args:expr),+) $($others:tt)*) => {
+            $crate::compose_stage!($fl, call, $f_left, $($args),+)
+        };
+
+        ($fl:ident; $f_left:ident,) => {
+            $f_left
+        };
+
+        ($fl:ident; $f_left:ident, .provide($p:expr) $($others:tt)*) => {
             {
-                let f_left = $f_left.then($f_right);
-                let is_left_fn_async = $isRightAsync || $is_left_fn_async;
-                let f3 = compose!(f_left,is_left_fn_async, -> $($others)*);
-                f3
+                let f = $crate::compose_stage!($fl, provide, $f_left, $p);
+                $crate::compose_pipeline!($fl; f, $($others)*)
             }
         };
 
-        ($f_left:ident,$is_left_fn_async:ident,-> $fn:ident.provide($p:expr) $($others:tt)*) =>{
+        ($fl:ident; $f_left:ident, .provide_at::<$n:literal>($p:expr) $($others:tt)*) => {
             {
-                let f4;
-                
-                crate::concat_idents!(lifted_fn_name = fn_composer__lifted_fn,_, $fn {
-                    paste!{
-                    let is_retryable = [< fn_composer__is_retryable_ $fn >]();
-                    let current_f = if !is_retryable{
-                        [<fn_composer__lifted_fn_ $fn>]($fn)
-                    }else {
-                        [<fn_composer__lifted_fn_ $fn>]([< fn_composer__ retry_ $fn>])
-                    };
-                    }
-                    crate::concat_idents!(asyn_check_fn = fn_composer__is_async_, $fn {
-                        let is_right_async = asyn_check_fn();
-                        let f_right = current_f.provide($p);
-                        let f3 = compose!($f_left,$is_left_fn_async,f_right,is_right_async,$($others)*);
-                        f4 = f3;
-                    });
-                });
-                f4
+                let f = $crate::compose_stage!($fl, provide_at, $n, $f_left, $p);
+                $crate::compose_pipeline!($fl; f, $($others)*)
             }
         };
 
-        ($f_left:ident,$isLeftFnAsync:ident,-> $fn:ident.provide($p:expr) $($others:tt)*) =>{
+        ($fl:ident; $f_left:ident, .provide_with($p:expr) $($others:tt)*) => {
             {
-                let f4;
-                crate::concat_idents!(lifted_fn_name = fn_composer__lifted_fn,_, $fn {
-                    let is_retryable = [<fn_composer__is_retryable $fn>]();
-                    let current_f = if !is_retryable{
-                        [<lifted_fn_name $fn>]($fn)
-                    }else {
-                        [<lifted_fn_name $fn>]([<fn_composer__ retry_ $fn>])
-                    };
-                    crate::concat_idents!(asyn_check_fn = fn_composer__is_async_, $fn {
-                        let isRightAsync = asyn_check_fn();
-                        let f_right = current_f.provide($p);
-                        let f3 = compose!($f_left,$is_left_fn_async,f_right,isRightAsync,$($others)*);
-                        f4 = f3;
-                    });
-                });
-                f4
+                let f = $crate::compose_stage!($fl, provide_with, $f_left, $p);
+                $crate::compose_pipeline!($fl; f, $($others)*)
             }
         };
 
+        ($fl:ident; $f_left:ident, .provide_async($p:expr) $($others:tt)*) => {
+            {
+                let f = $crate::compose_stage!($fl, provide_async, $f_left, $p);
+                $crate::compose_pipeline!($fl; f, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, .provide_first($p:expr) $($others:tt)*) => {
+            $crate::compose_pipeline!($fl; $f_left, .provide_at::<0>($p) $($others)*)
+        };
 
-        ($f_left:ident,$is_left_fn_async:ident,-> $fn:ident $($others:tt)*) =>{
+        ($fl:ident; $f_left:ident, ->> $fn:tt $($others:tt)*) => {
             {
-                let f4;
-                paste!{
-
-                    let asyn_check_fn = [<fn_composer__is_async_ $fn>];
-                    let current_async = asyn_check_fn();
-                    let _is_result_async = current_async || $is_left_fn_async;
-                    let is_retryable = [<fn_composer__is_retryable_ $fn>]();
-                    let current_f = if !is_retryable{
-                        [<fn_composer__lifted_fn_ $fn>]($fn)
-                    }else {
-                        [<fn_composer__lifted_fn_ $fn>]([<fn_composer__ retry_ $fn>])
-                    };
-                    let f3 = $f_left.then(current_f);
-                    let f3 = compose!(f3,_is_result_async,$($others)*);
-                    f4 = f3;
-                }
-                f4
+                let f_left = $crate::Spread($f_left);
+                let current_f = $crate::compose_stage!($fl, lift, $fn);
+                $crate::compose_pipeline!($fl; f_left, current_f, $($others)*)
             }
         };
 
+        ($fl:ident; $f_left:ident, -> @method($obj:ident . $method:ident) $($others:tt)*) => {
+            {
+                let current_f = $crate::compose_stage!($fl, method, $obj, $method);
+                $crate::compose_pipeline!($fl; $f_left, current_f, $($others)*)
+            }
+        };
 
+        ($fl:ident; $f_left:ident, -> @sync_closure($($closure:tt)*) $($others:tt)*) => {
+            {
+                let current_f = $crate::compose_stage!($fl, sync_closure, $f_left, $($closure)*);
+                $crate::compose_pipeline!($fl; $f_left, current_f, $($others)*)
+            }
+        };
 
-        ($fn:ident $($others:tt)*) => {
+        ($fl:ident; $f_left:ident, -> @async_closure($($closure:tt)*) $($others:tt)*) => {
             {
+                let current_f = $crate::compose_stage!($fl, async_closure, $f_left, $($closure)*);
+                $crate::compose_pipeline!($fl; $f_left, current_f, $($others)*)
+            }
+        };
 
-                use Then;
-                let f2;
-                paste!{
-                    let f = [<fn_composer__lifted_fn_ $fn>]($fn);
-                    let is_async = [<fn_composer__is_async_ $fn>]();
-                    let is_retryable = [<fn_composer__is_retryable_ $fn>]();
-                    let f = if !is_retryable{
-                        [<fn_composer__lifted_fn_ $fn>]($fn)
-                    }else {
-                        [<fn_composer__lifted_fn_ $fn>]([<fn_composer__ retry_ $fn>])
-                    };
-                    let f1 = compose!(f,is_async,$($others)*);
-                    f2 = f1;
-                };
-                f2
+        ($fl:ident; $f_left:ident, -> join($($fn:ident $(:: $fn_path:ident)* $(:: < $($fn_ty:ty),+ >)?),+) $($others:tt)*) => {
+            {
+                let current_f = $crate::compose_stage!($fl, join, ($( $crate::compose_stage!($fl, lift, ($fn $(:: $fn_path)* $(::<$($fn_ty),+>)?)), )+));
+                $crate::compose_pipeline!($fl; $f_left, current_f, $($others)*)
             }
         };
 
+        ($fl:ident; $f_left:ident, -> branch($pred:expr, $then_fn:ident $(:: $then_path:ident)* $(:: < $($then_ty:ty),+ >)?, $else_fn:ident $(:: $else_path:ident)* $(:: < $($else_ty:ty),+ >)?) $($others:tt)*) => {
+            {
+                let current_f = $crate::compose_stage!($fl, branch, $pred,
+                    $crate::compose_stage!($fl, lift, ($then_fn $(:: $then_path)* $(::<$($then_ty),+>)?)),
+                    $crate::compose_stage!($fl, lift, ($else_fn $(:: $else_path)* $(::<$($else_ty),+>)?)));
+                $crate::compose_pipeline!($fl; $f_left, current_f, $($others)*)
+            }
+        };
 
+        ($fl:ident; $f_left:ident, -> or_else($fallback_fn:ident $(:: $fallback_path:ident)* $(:: < $($fallback_ty:ty),+ >)?) $($others:tt)*) => {
+            {
+                let fallback_f = $crate::compose_stage!($fl, lift, ($fallback_fn $(:: $fallback_path)* $(::<$($fallback_ty),+>)?));
+                let f = $crate::compose_stage!($fl, or_else, $f_left, fallback_f);
+                $crate::compose_pipeline!($fl; f, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, -> $stage:ident($($args:tt)*) -> or_else $($others:tt)*) => {
+            compile_error!(concat!("or_else must follow the function whose input is passed to the fallback, found it after ", stringify!($stage)))
+        };
+
+        ($fl:ident; $f_left:ident, -> recover($handler:expr) $($others:tt)*) => {
+            {
+                let f = $crate::compose_stage!($fl, recover, $f_left, $handler);
+                $crate::compose_pipeline!($fl; f, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, -> map_err($mapper:expr) $($others:tt)*) => {
+            {
+                let f = $crate::compose_stage!($fl, map_err, $f_left, $mapper);
+                $crate::compose_pipeline!($fl; f, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, -> timeout($duration:expr) $($others:tt)*) => {
+            {
+                let f = $crate::compose_stage!($fl, timeout, $f_left, $duration);
+                $crate::compose_pipeline!($fl; f, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, -> $fn:tt $($others:tt)*) => {
+            {
+                let current_f = $crate::compose_stage!($fl, lift, $fn);
+                $crate::compose_pipeline!($fl; $f_left, current_f, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, $f_right:ident, .provide($p:expr) $($others:tt)*) => {
+            {
+                let f_right = $crate::compose_stage!($fl, provide, $f_right, $p);
+                $crate::compose_pipeline!($fl; $f_left, f_right, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, $f_right:ident, .provide_at::<$n:literal>($p:expr) $($others:tt)*) => {
+            {
+                let f_right = $crate::compose_stage!($fl, provide_at, $n, $f_right, $p);
+                $crate::compose_pipeline!($fl; $f_left, f_right, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, $f_right:ident, .provide_with($p:expr) $($others:tt)*) => {
+            {
+                let f_right = $crate::compose_stage!($fl, provide_with, $f_right, $p);
+                $crate::compose_pipeline!($fl; $f_left, f_right, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, $f_right:ident, .provide_async($p:expr) $($others:tt)*) => {
+            {
+                let f_right = $crate::compose_stage!($fl, provide_async, $f_right, $p);
+                $crate::compose_pipeline!($fl; $f_left, f_right, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, $f_right:ident, .provide_first($p:expr) $($others:tt)*) => {
+            $crate::compose_pipeline!($fl; $f_left, $f_right, .provide_at::<0>($p) $($others)*)
+        };
+
+        ($fl:ident; $f_left:ident, $f_right:ident, -> or_else($fallback_fn:ident $(:: $fallback_path:ident)* $(:: < $($fallback_ty:ty),+ >)?) $($others:tt)*) => {
+            {
+                let fallback_f = $crate::compose_stage!($fl, lift, ($fallback_fn $(:: $fallback_path)* $(::<$($fallback_ty),+>)?));
+                let f_right = $crate::compose_stage!($fl, or_else, $f_right, fallback_f);
+                $crate::compose_pipeline!($fl; $f_left, f_right, $($others)*)
+            }
+        };
+
+        ($fl:ident; $f_left:ident, $f_right:ident, $($others:tt)*) => {
+            {
+                let f_left = $crate::compose_stage!($fl, then, $f_left, $f_right);
+                $crate::compose_pipeline!($fl; f_left, $($others)*)
+            }
+        };
     }
-}
 
+
+    /// Builds a single stage of a pipeline parsed by [`compose_pipeline!`] for the given flavour.
+    ///
+    /// The `boxed` and `shared` flavours use the same traits, the `step` flavour wraps the steps into their statically typed counterparts.
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! compose_stage {
+        (boxed, lift, $fn:tt) => {
+            $crate::fn_composer_path!(lifted_fn_, $fn)($crate::fn_composer_path!(retry_, $fn))
+        };
+
+        (shared, lift, $fn:tt) => {
+            $crate::fn_composer_path!(lifted_shared_fn_, $fn)($crate::fn_composer_path!(retry_, $fn))
+        };
+
+        (step, lift, $fn:tt) => {
+            $crate::fn_composer_path!(lifted_step_, $fn)($crate::fn_composer_path!(retry_, $fn))
+        };
+
+        (boxed, method, $obj:ident, $method:ident) => {
+            $crate::paste!{ $obj.[<fn_composer__bound_ $method>]() }
+        };
+
+        (shared, method, $obj:ident, $method:ident) => {
+            compile_error!(concat!("methods of a value cannot be shared, compose `<Type>::", stringify!($method), "` and provide the value instead"))
+        };
+
+        (step, method, $obj:ident, $method:ident) => {
+            $crate::paste!{ $obj.[<fn_composer__bound_step_ $method>]() }
+        };
+
+        (boxed, sync_closure, $f_left:ident, $($closure:tt)*) => {
+            $crate::lift_closure(&$f_left, $($closure)*)
+        };
+
+        (shared, sync_closure, $f_left:ident, $($closure:tt)*) => {
+            $crate::lift_shared_closure(&$f_left, $($closure)*)
+        };
+
+        (step, sync_closure, $f_left:ident, $($closure:tt)*) => {
+            $crate::closure_step(&$f_left, $($closure)*)
+        };
+
+        (boxed, async_closure, $f_left:ident, $($closure:tt)*) => {
+            $crate::lift_async_closure(&$f_left, $($closure)*)
+        };
+
+        (shared, async_closure, $f_left:ident, $($closure:tt)*) => {
+            $crate::lift_shared_async_closure(&$f_left, $($closure)*)
+        };
+
+        (step, async_closure, $f_left:ident, $($closure:tt)*) => {
+            $crate::async_closure_step(&$f_left, $($closure)*)
+        };
+
+        (boxed, join, $fns:expr) => {
+            $crate::join($fns)
+        };
+
+        (shared, join, $fns:expr) => {
+            $crate::shared_join($fns)
+        };
+
+        (step, join, $fns:expr) => {
+            $crate::Joined::new($fns)
+        };
+
+        (step, call, $f:ident, $($args:expr),+) => {
+            $crate::Step::call($f, ($($args,)+))
+        };
+
+        (step, then, $f_left:ident, $f_right:ident) => {
+            $crate::Composed::new($f_left, $f_right)
+        };
+
+        (step, branch, $pred:expr, $then_f:expr, $else_f:expr) => {
+            $crate::Branched::new($pred, $then_f, $else_f)
+        };
+
+        (step, provide, $f:ident, $p:expr) => {
+            $crate::Provide::new($f, $p)
+        };
+
+        (step, provide_at, $n:literal, $f:ident, $p:expr) => {
+            $crate::ProvideAt::<_, _, $n>::new($f, $p)
+        };
+
+        (step, provide_with, $f:ident, $p:expr) => {
+            $crate::ProvideWith::new($f, $p)
+        };
+
+        (step, provide_async, $f:ident, $p:expr) => {
+            $crate::ProvideAsync::new($f, $p)
+        };
+
+        (step, recover, $f:ident, $handler:expr) => {
+            $crate::Recovered::new($f, $handler)
+        };
+
+        (step, map_err, $f:ident, $mapper:expr) => {
+            $crate::MappedErr::new($f, $mapper)
+        };
+
+        (step, timeout, $f:ident, $duration:expr) => {
+            $crate::TimeLimited::new($f, $duration)
+        };
+
+        (step, or_else, $f:ident, $fallback:ident) => {
+            $crate::WithFallback::new($f, $fallback)
+        };
+
+        ($fl:ident, call, $f:ident, $($args:expr),+) => {
+            $f($($args),+)
+        };
+
+        ($fl:ident, then, $f_left:ident, $f_right:ident) => {
+            $crate::Then::then($f_left, $f_right)
+        };
+
+        ($fl:ident, branch, $pred:expr, $then_f:expr, $else_f:expr) => {
+            $crate::branch($pred, $then_f, $else_f)
+        };
+
+        ($fl:ident, provide, $f:ident, $p:expr) => {
+            $crate::Injector::provide($f, $p)
+        };
+
+        ($fl:ident, provide_at, $n:literal, $f:ident, $p:expr) => {
+            $crate::InjectorAt::<$n, _, _>::provide_at($f, $p)
+        };
+
+        ($fl:ident, provide_with, $f:ident, $p:expr) => {
+            $crate::LazyInjector::provide_with($f, $p)
+        };
+
+        ($fl:ident, provide_async, $f:ident, $p:expr) => {
+            $crate::AsyncInjector::provide_async($f, $p)
+        };
+
+        ($fl:ident, recover, $f:ident, $handler:expr) => {
+            $crate::Recover::recover($f, $handler)
+        };
+
+        ($fl:ident, map_err, $f:ident, $mapper:expr) => {
+            $crate::MapErr::map_err($f, $mapper)
+        };
+
+        ($fl:ident, timeout, $f:ident, $duration:expr) => {
+            $crate::Timeout::timeout($f, $duration)
+        };
+
+        ($fl:ident, or_else, $f:ident, $fallback:ident) => {
+            $crate::OrElse::or_else($f, $fallback)
+        };
+    }
+}
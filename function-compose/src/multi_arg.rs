@@ -0,0 +1,153 @@
+//! Composing functions with more than one argument at the head of the pipeline.
+//!
+//! The composed function accepts the same arguments as the first function, e.g `compose!(add_3 -> add_10)` returns a
+//! `BoxedFn3` and `compose!(add_3 -> add_10 -> with_args(1, 2, 3))` calls it with the three arguments.
+
+use std::sync::Arc;
+
+use futures::FutureExt;
+use paste::paste;
+
+use crate::*;
+
+macro_rules! impl_multi_arg_then {
+    ([$($args:ident),*], $arg_size:literal) => {
+        paste!{
+            #[doc = concat!("Then implementation for composing sync function (BoxedFn", stringify!($arg_size), ") with sync function(BoxedFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, BoxedFn1<'a, B, C, E2>, [<BoxedFn $arg_size>]<'a, $($args,)* C, E2>> for [<BoxedFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: BoxedFn1<'a, B, C, E2>) -> [<BoxedFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Box::new(move |$([<$args:lower>]: $args),*| match self($([<$args:lower>]),*) {
+                        Ok(inner_result) => f(inner_result),
+                        Err(error) => Err(to_fn_error(error)),
+                    })
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing sync function (BoxedFn", stringify!($arg_size), ") with async function(BoxedAsyncFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, BoxedAsyncFn1<'a, B, C, E2>, [<BoxedAsyncFn $arg_size>]<'a, $($args,)* C, E2>> for [<BoxedFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: BoxedAsyncFn1<'a, B, C, E2>) -> [<BoxedAsyncFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Box::new(move |$([<$args:lower>]: $args),*| async move {
+                        match self($([<$args:lower>]),*) {
+                            Ok(inner_result) => f(inner_result).await,
+                            Err(error) => Err(to_fn_error(error)),
+                        }
+                    }.boxed())
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing async function (BoxedAsyncFn", stringify!($arg_size), ") with sync function(BoxedFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, BoxedFn1<'a, B, C, E2>, [<BoxedAsyncFn $arg_size>]<'a, $($args,)* C, E2>> for [<BoxedAsyncFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: BoxedFn1<'a, B, C, E2>) -> [<BoxedAsyncFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Box::new(move |$([<$args:lower>]: $args),*| async move {
+                        match self($([<$args:lower>]),*).await {
+                            Ok(inner_result) => f(inner_result),
+                            Err(error) => Err(to_fn_error(error)),
+                        }
+                    }.boxed())
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing async function (BoxedAsyncFn", stringify!($arg_size), ") with async function(BoxedAsyncFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, BoxedAsyncFn1<'a, B, C, E2>, [<BoxedAsyncFn $arg_size>]<'a, $($args,)* C, E2>> for [<BoxedAsyncFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: BoxedAsyncFn1<'a, B, C, E2>) -> [<BoxedAsyncFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Box::new(move |$([<$args:lower>]: $args),*| async move {
+                        match self($([<$args:lower>]),*).await {
+                            Ok(inner_result) => f(inner_result).await,
+                            Err(error) => Err(to_fn_error(error)),
+                        }
+                    }.boxed())
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing shared sync function (SharedFn", stringify!($arg_size), ") with shared sync function(SharedFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, SharedFn1<'a, B, C, E2>, [<SharedFn $arg_size>]<'a, $($args,)* C, E2>> for [<SharedFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: SharedFn1<'a, B, C, E2>) -> [<SharedFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| match self($([<$args:lower>]),*) {
+                        Ok(inner_result) => f(inner_result),
+                        Err(error) => Err(to_fn_error(error)),
+                    })
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing shared sync function (SharedFn", stringify!($arg_size), ") with shared async function(SharedAsyncFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, SharedAsyncFn1<'a, B, C, E2>, [<SharedAsyncFn $arg_size>]<'a, $($args,)* C, E2>> for [<SharedFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: SharedAsyncFn1<'a, B, C, E2>) -> [<SharedAsyncFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| {
+                        let g = self.clone();
+                        let f = f.clone();
+                        async move {
+                            match g($([<$args:lower>]),*) {
+                                Ok(inner_result) => f(inner_result).await,
+                                Err(error) => Err(to_fn_error(error)),
+                            }
+                        }.boxed()
+                    })
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing shared async function (SharedAsyncFn", stringify!($arg_size), ") with shared sync function(SharedFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, SharedFn1<'a, B, C, E2>, [<SharedAsyncFn $arg_size>]<'a, $($args,)* C, E2>> for [<SharedAsyncFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: SharedFn1<'a, B, C, E2>) -> [<SharedAsyncFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| {
+                        let g = self.clone();
+                        let f = f.clone();
+                        async move {
+                            match g($([<$args:lower>]),*).await {
+                                Ok(inner_result) => f(inner_result),
+                                Err(error) => Err(to_fn_error(error)),
+                            }
+                        }.boxed()
+                    })
+                }
+            }
+
+            #[doc = concat!("Then implementation for composing shared async function (SharedAsyncFn", stringify!($arg_size), ") with shared async function(SharedAsyncFn1)")]
+            impl<'a, $($args: 'a + Send,)* B: 'a + Send, C: 'a, E1: Send + 'a, E2: Send + 'a>
+                Then<'a, ($($args,)*), B, C, SharedAsyncFn1<'a, B, C, E2>, [<SharedAsyncFn $arg_size>]<'a, $($args,)* C, E2>> for [<SharedAsyncFn $arg_size>]<'a, $($args,)* B, E1> where E2: From<E1> {
+
+                fn then(self, f: SharedAsyncFn1<'a, B, C, E2>) -> [<SharedAsyncFn $arg_size>]<'a, $($args,)* C, E2> {
+                    Arc::new(move |$([<$args:lower>]: $args),*| {
+                        let g = self.clone();
+                        let f = f.clone();
+                        async move {
+                            match g($([<$args:lower>]),*).await {
+                                Ok(inner_result) => f(inner_result).await,
+                                Err(error) => Err(to_fn_error(error)),
+                            }
+                        }.boxed()
+                    })
+                }
+            }
+        }
+    };
+}
+
+impl_multi_arg_then!([T1, T2], 2);
+impl_multi_arg_then!([T1, T2, T3], 3);
+impl_multi_arg_then!([T1, T2, T3, T4], 4);
+impl_multi_arg_then!([T1, T2, T3, T4, T5], 5);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6], 6);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7], 7);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8], 8);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9], 9);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], 10);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], 11);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], 12);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], 13);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], 14);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15);
+impl_multi_arg_then!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16);
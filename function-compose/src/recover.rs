@@ -0,0 +1,311 @@
+//! Error handling stages.
+//!
+//! [`Recover`] turns the error of a function into an Ok value, [`MapErr`] converts the error of a function
+//! into another error type and [`OrElse`] calls a fallback function with the same input when a function fails.
+//! Recover and MapErr apply to the function composed so far, OrElse is applied by the compose macros to the function just before it.
+//! They are implemented for the functions with a single argument, the statically typed counterparts accept any [`Step`].
+
+use std::sync::Arc;
+
+use futures::FutureExt;
+
+use crate::{
+    BoxedAsyncFn1, BoxedFn1, IntoBoxedAsyncFn1, IntoSharedAsyncFn1, SharedAsyncFn1, SharedFn1, Step,
+};
+
+/// Converts the error returned by a function into an Ok value using the handler.
+///
+/// Type param B is the return type and E is the error type of Self
+#[diagnostic::on_unimplemented(
+    message = "recover can be applied only to functions with a single argument, found `{Self}`",
+    note = "provide the other arguments before recover, or use compose_static! which accepts any number of arguments"
+)]
+pub trait Recover<'a, B, E> {
+    fn recover<H>(self, handler: H) -> Self
+    where
+        H: Fn(E) -> B + Send + Sync + 'a;
+}
+
+/// Converts the error returned by a function into another error type using the mapper.
+///
+/// Type param E is the error type of Self. Mapped is the function type with the new error type.
+#[diagnostic::on_unimplemented(
+    message = "map_err can be applied only to functions with a single argument, found `{Self}`",
+    note = "provide the other arguments before map_err, or use compose_static! which accepts any number of arguments"
+)]
+pub trait MapErr<'a, E> {
+    type Mapped<E2: Send + 'a>;
+
+    fn map_err<E2, H>(self, mapper: H) -> Self::Mapped<E2>
+    where
+        E2: Send + 'a,
+        H: Fn(E) -> E2 + Send + Sync + 'a;
+}
+
+/// Calls the fallback function with a clone of the input when self returns an error.
+///
+/// Both functions must have the same input and return type. The error of the fallback function is returned when both fail.
+/// Type param R is the resulting function type.
+#[diagnostic::on_unimplemented(
+    message = "or_else can be applied only to functions with a single argument and a fallback with the same argument and Ok type, found `{Self}`",
+    note = "provide the other arguments before or_else, or use compose_static! which accepts any number of arguments"
+)]
+pub trait OrElse<'a, F, R> {
+    fn or_else(self, fallback: F) -> R;
+}
+
+impl<'a, A: 'a, B: 'a, E: 'a> Recover<'a, B, E> for BoxedFn1<'a, A, B, E> {
+    fn recover<H>(self, handler: H) -> Self
+    where
+        H: Fn(E) -> B + Send + Sync + 'a,
+    {
+        Box::new(move |a: A| self(a).or_else(|error| Ok(handler(error))))
+    }
+}
+
+impl<'a, A: 'a, B: Send + 'a, E: Send + 'a> Recover<'a, B, E> for BoxedAsyncFn1<'a, A, B, E> {
+    fn recover<H>(self, handler: H) -> Self
+    where
+        H: Fn(E) -> B + Send + Sync + 'a,
+    {
+        Box::new(move |a: A| {
+            let result = self(a);
+            async move { result.await.or_else(|error| Ok(handler(error))) }.boxed()
+        })
+    }
+}
+
+impl<'a, A: 'a, B: 'a, E: 'a> Recover<'a, B, E> for SharedFn1<'a, A, B, E> {
+    fn recover<H>(self, handler: H) -> Self
+    where
+        H: Fn(E) -> B + Send + Sync + 'a,
+    {
+        Arc::new(move |a: A| self(a).or_else(|error| Ok(handler(error))))
+    }
+}
+
+impl<'a, A: 'a, B: Send + 'a, E: Send + 'a> Recover<'a, B, E> for SharedAsyncFn1<'a, A, B, E> {
+    fn recover<H>(self, handler: H) -> Self
+    where
+        H: Fn(E) -> B + Send + Sync + 'a,
+    {
+        let handler = Arc::new(handler);
+        Arc::new(move |a: A| {
+            let result = self(a);
+            let handler = handler.clone();
+            async move { result.await.or_else(|error| Ok(handler(error))) }.boxed()
+        })
+    }
+}
+
+impl<'a, A: 'a, B: 'a, E: 'a> MapErr<'a, E> for BoxedFn1<'a, A, B, E> {
+    type Mapped<E2: Send + 'a> = BoxedFn1<'a, A, B, E2>;
+
+    fn map_err<E2, H>(self, mapper: H) -> Self::Mapped<E2>
+    where
+        E2: Send + 'a,
+        H: Fn(E) -> E2 + Send + Sync + 'a,
+    {
+        Box::new(move |a: A| self(a).map_err(mapper))
+    }
+}
+
+impl<'a, A: 'a, B: Send + 'a, E: Send + 'a> MapErr<'a, E> for BoxedAsyncFn1<'a, A, B, E> {
+    type Mapped<E2: Send + 'a> = BoxedAsyncFn1<'a, A, B, E2>;
+
+    fn map_err<E2, H>(self, mapper: H) -> Self::Mapped<E2>
+    where
+        E2: Send + 'a,
+        H: Fn(E) -> E2 + Send + Sync + 'a,
+    {
+        Box::new(move |a: A| {
+            let result = self(a);
+            async move { result.await.map_err(mapper) }.boxed()
+        })
+    }
+}
+
+impl<'a, A: 'a, B: 'a, E: 'a> MapErr<'a, E> for SharedFn1<'a, A, B, E> {
+    type Mapped<E2: Send + 'a> = SharedFn1<'a, A, B, E2>;
+
+    fn map_err<E2, H>(self, mapper: H) -> Self::Mapped<E2>
+    where
+        E2: Send + 'a,
+        H: Fn(E) -> E2 + Send + Sync + 'a,
+    {
+        Arc::new(move |a: A| self(a).map_err(&mapper))
+    }
+}
+
+impl<'a, A: 'a, B: Send + 'a, E: Send + 'a> MapErr<'a, E> for SharedAsyncFn1<'a, A, B, E> {
+    type Mapped<E2: Send + 'a> = SharedAsyncFn1<'a, A, B, E2>;
+
+    fn map_err<E2, H>(self, mapper: H) -> Self::Mapped<E2>
+    where
+        E2: Send + 'a,
+        H: Fn(E) -> E2 + Send + Sync + 'a,
+    {
+        let mapper = Arc::new(mapper);
+        Arc::new(move |a: A| {
+            let result = self(a);
+            let mapper = mapper.clone();
+            async move { result.await.map_err(|error| mapper(error)) }.boxed()
+        })
+    }
+}
+
+impl<'a, A: Clone + 'a, B: 'a, E1: 'a, E2: 'a> OrElse<'a, BoxedFn1<'a, A, B, E2>, BoxedFn1<'a, A, B, E2>> for BoxedFn1<'a, A, B, E1> {
+    fn or_else(self, fallback: BoxedFn1<'a, A, B, E2>) -> BoxedFn1<'a, A, B, E2> {
+        Box::new(move |a: A| self(a.clone()).or_else(|_| fallback(a)))
+    }
+}
+
+impl<'a, A: Clone + 'a, B: 'a, E1: 'a, E2: 'a> OrElse<'a, SharedFn1<'a, A, B, E2>, SharedFn1<'a, A, B, E2>> for SharedFn1<'a, A, B, E1> {
+    fn or_else(self, fallback: SharedFn1<'a, A, B, E2>) -> SharedFn1<'a, A, B, E2> {
+        Arc::new(move |a: A| self(a.clone()).or_else(|_| fallback(a)))
+    }
+}
+
+macro_rules! impl_async_or_else {
+    ($fn_type:ident, $fallback_type:ident) => {
+        #[doc = concat!("OrElse implementation for ", stringify!($fn_type), " with ", stringify!($fallback_type), " fallback")]
+        impl<'a, A: Clone + Send + 'a, B: Send + 'a, E1: Send + 'a, E2: Send + 'a> OrElse<'a, $fallback_type<'a, A, B, E2>, BoxedAsyncFn1<'a, A, B, E2>> for $fn_type<'a, A, B, E1> {
+            fn or_else(self, fallback: $fallback_type<'a, A, B, E2>) -> BoxedAsyncFn1<'a, A, B, E2> {
+                let f = self.into_boxed_async_fn1();
+                let fallback = fallback.into_boxed_async_fn1();
+                Box::new(move |a: A| {
+                    let result = f(a.clone());
+                    async move {
+                        match result.await {
+                            Ok(value) => Ok(value),
+                            Err(_) => fallback(a).await,
+                        }
+                    }.boxed()
+                })
+            }
+        }
+    };
+}
+
+macro_rules! impl_shared_async_or_else {
+    ($fn_type:ident, $fallback_type:ident) => {
+        #[doc = concat!("OrElse implementation for ", stringify!($fn_type), " with ", stringify!($fallback_type), " fallback")]
+        impl<'a, A: Clone + Send + 'a, B: Send + 'a, E1: Send + 'a, E2: Send + 'a> OrElse<'a, $fallback_type<'a, A, B, E2>, SharedAsyncFn1<'a, A, B, E2>> for $fn_type<'a, A, B, E1> {
+            fn or_else(self, fallback: $fallback_type<'a, A, B, E2>) -> SharedAsyncFn1<'a, A, B, E2> {
+                let f = self.into_shared_async_fn1();
+                let fallback = fallback.into_shared_async_fn1();
+                Arc::new(move |a: A| {
+                    let result = f(a.clone());
+                    let fallback = fallback.clone();
+                    async move {
+                        match result.await {
+                            Ok(value) => Ok(value),
+                            Err(_) => fallback(a).await,
+                        }
+                    }.boxed()
+                })
+            }
+        }
+    };
+}
+
+impl_async_or_else!(BoxedFn1, BoxedAsyncFn1);
+impl_async_or_else!(BoxedAsyncFn1, BoxedFn1);
+impl_async_or_else!(BoxedAsyncFn1, BoxedAsyncFn1);
+
+impl_shared_async_or_else!(SharedFn1, SharedAsyncFn1);
+impl_shared_async_or_else!(SharedAsyncFn1, SharedFn1);
+impl_shared_async_or_else!(SharedAsyncFn1, SharedAsyncFn1);
+
+/// Step converting the error of the underlying step into an Ok value, the statically typed counterpart of [`Recover`]
+#[derive(Clone, Copy)]
+pub struct Recovered<S, H> {
+    step: S,
+    handler: H,
+}
+
+impl<S, H> Recovered<S, H>
+where
+    S: Step,
+    H: FnOnce(S::Error) -> S::Output,
+{
+    pub fn new(step: S, handler: H) -> Self {
+        Recovered { step, handler }
+    }
+}
+
+impl<S, H> Step for Recovered<S, H>
+where
+    S: Step,
+    H: FnOnce(S::Error) -> S::Output,
+{
+    type Input = S::Input;
+    type Output = S::Output;
+    type Error = S::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        self.step.call(input).await.or_else(|error| Ok((self.handler)(error)))
+    }
+}
+
+/// Step converting the error of the underlying step into another error, the statically typed counterpart of [`MapErr`]
+#[derive(Clone, Copy)]
+pub struct MappedErr<S, H> {
+    step: S,
+    mapper: H,
+}
+
+impl<S, H, E2> MappedErr<S, H>
+where
+    S: Step,
+    H: FnOnce(S::Error) -> E2,
+{
+    pub fn new(step: S, mapper: H) -> Self {
+        MappedErr { step, mapper }
+    }
+}
+
+impl<S, H, E2> Step for MappedErr<S, H>
+where
+    S: Step,
+    H: FnOnce(S::Error) -> E2,
+{
+    type Input = S::Input;
+    type Output = S::Output;
+    type Error = E2;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        self.step.call(input).await.map_err(self.mapper)
+    }
+}
+
+/// Step calling the fallback step with a clone of the input when the underlying step fails, the statically typed counterpart of [`OrElse`]
+#[derive(Clone, Copy)]
+pub struct WithFallback<S1, S2> {
+    step: S1,
+    fallback: S2,
+}
+
+impl<S1, S2> WithFallback<S1, S2> {
+    pub fn new(step: S1, fallback: S2) -> Self {
+        WithFallback { step, fallback }
+    }
+}
+
+impl<S1, S2> Step for WithFallback<S1, S2>
+where
+    S1: Step,
+    S1::Input: Clone,
+    S2: Step<Input = S1::Input, Output = S1::Output>,
+{
+    type Input = S1::Input;
+    type Output = S1::Output;
+    type Error = S2::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        match self.step.call(input.clone()).await {
+            Ok(value) => Ok(value),
+            Err(_) => self.fallback.call(input).await,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Retry loop of composeable functions configured with `#[composeable(retry = ..)]`.
+//!
+//! The delay strategy is any iterator of durations, e.g the strategies of this module(`Fixed::from_millis(100).take(3)`).
+//! The function is attempted once more after every delay, errors for which `retry_if` does not hold are not retried.
+
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+mod delay;
+pub use delay::*;
+
+/// Error of a retried function which failed on every attempt.
+///
+/// Returned to the caller of a function composeable with `report_exhausted`, the error type of the function must implement
+/// `From<RetryExhausted<E>>`.
+#[derive(Debug, Clone)]
+pub struct RetryExhausted<E> {
+    /// number of times the function was called
+    pub attempts: usize,
+    /// time from the first attempt until the last attempt failed
+    pub elapsed: Duration,
+    /// error returned by the last attempt
+    pub last_error: E,
+}
+
+impl<E: Display> Display for RetryExhausted<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed after {} attempts in {:?}: {}", self.attempts, self.elapsed, self.last_error)
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for RetryExhausted<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.last_error)
+    }
+}
+
+/// Error of a retried function
+#[derive(Debug, Clone)]
+pub enum RetryError<E> {
+    /// the error was not retried, see `retry_if`
+    Aborted(E),
+    /// every attempt failed
+    Exhausted(RetryExhausted<E>),
+}
+
+impl<E> RetryError<E> {
+    /// Whether every attempt failed, i.e the error is one `retry_if` holds for
+    pub fn is_exhausted(&self) -> bool {
+        matches!(self, RetryError::Exhausted(_))
+    }
+
+    /// The error returned by the last attempt
+    pub fn last_error(&self) -> &E {
+        match self {
+            RetryError::Aborted(error) => error,
+            RetryError::Exhausted(exhausted) => &exhausted.last_error,
+        }
+    }
+
+    /// The error returned by the last attempt
+    pub fn into_last_error(self) -> E {
+        match self {
+            RetryError::Aborted(error) => error,
+            RetryError::Exhausted(exhausted) => exhausted.last_error,
+        }
+    }
+
+    /// The error returned by the last attempt, converted from RetryExhausted if every attempt failed
+    pub fn report_exhausted(self) -> E
+    where
+        E: From<RetryExhausted<E>>,
+    {
+        match self {
+            RetryError::Aborted(error) => error,
+            RetryError::Exhausted(exhausted) => From::from(exhausted),
+        }
+    }
+}
+
+/// Owned argument of a retried function, which is cloned for every attempt
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` must implement Clone to be an owned argument of a retried function",
+    label = "cloned for every attempt",
+    note = "derive Clone for `{Self}` or take the argument by reference"
+)]
+pub trait RetryArgument: Clone {}
+
+impl<T: Clone> RetryArgument for T {}
+
+/// Clones an owned argument of a retried function for an attempt
+pub fn clone_for_retry<T: RetryArgument>(argument: &T) -> T {
+    argument.clone()
+}
+
+/// Calls `operation` until it succeeds, `condition` does not hold for its error or the delays of `strategy` are exhausted.
+///
+/// `on_retry` is called with the number of the failed attempt(starting at 1), the delay before the next attempt and the error.
+pub fn retry_sync<T, E, S, Op, C, H>(strategy: S, mut operation: Op, mut condition: C, mut on_retry: H) -> Result<T, RetryError<E>>
+where
+    S: IntoIterator<Item = Duration>,
+    Op: FnMut() -> Result<T, E>,
+    C: FnMut(&E) -> bool,
+    H: FnMut(usize, Duration, &E),
+{
+    let started = Instant::now();
+    let mut delays = strategy.into_iter();
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let error = match operation() {
+            Ok(result) => return Ok(result),
+            Err(error) => error,
+        };
+        if !condition(&error) {
+            return Err(RetryError::Aborted(error));
+        }
+        match delays.next() {
+            Some(delay) => {
+                on_retry(attempts, delay, &error);
+                std::thread::sleep(delay);
+            }
+            None => return Err(RetryError::Exhausted(RetryExhausted { attempts, elapsed: started.elapsed(), last_error: error })),
+        }
+    }
+}
+
+/// Async version of [`retry_sync`], the delays are awaited with `sleep`(e.g [`runtime::sleep`](crate::runtime::sleep))
+pub async fn retry_async<T, E, S, Op, Fut, C, H, Sl, SlFut>(
+    strategy: S,
+    mut operation: Op,
+    mut condition: C,
+    mut on_retry: H,
+    mut sleep: Sl,
+) -> Result<T, RetryError<E>>
+where
+    S: IntoIterator<Item = Duration>,
+    Op: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    C: FnMut(&E) -> bool,
+    H: FnMut(usize, Duration, &E),
+    Sl: FnMut(Duration) -> SlFut,
+    SlFut: Future<Output = ()>,
+{
+    let started = Instant::now();
+    let mut delays = strategy.into_iter();
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let error = match operation().await {
+            Ok(result) => return Ok(result),
+            Err(error) => error,
+        };
+        if !condition(&error) {
+            return Err(RetryError::Aborted(error));
+        }
+        match delays.next() {
+            Some(delay) => {
+                on_retry(attempts, delay, &error);
+                sleep(delay).await;
+            }
+            None => return Err(RetryError::Exhausted(RetryExhausted { attempts, elapsed: started.elapsed(), last_error: error })),
+        }
+    }
+}
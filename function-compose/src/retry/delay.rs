@@ -0,0 +1,156 @@
+//! Delay strategies of retried functions.
+//!
+//! Every strategy is an endless iterator of durations, limit the number of retries with `take` or `max_elapsed`,
+//! e.g `Exponential::from_millis(10).map(jitter).take(5)`.
+
+use std::cell::Cell;
+use std::hash::{BuildHasher, RandomState};
+use std::time::{Duration, Instant};
+
+/// Same delay before every retry
+#[derive(Debug, Clone)]
+pub struct Fixed {
+    delay: Duration,
+}
+
+impl Fixed {
+    /// Waits `millis` milliseconds before every retry
+    pub fn from_millis(millis: u64) -> Fixed {
+        Fixed { delay: Duration::from_millis(millis) }
+    }
+}
+
+impl From<Duration> for Fixed {
+    fn from(delay: Duration) -> Fixed {
+        Fixed { delay }
+    }
+}
+
+impl Iterator for Fixed {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        Some(self.delay)
+    }
+}
+
+/// Delay multiplied by a factor after every retry.
+///
+/// As in the `retry` crate, [`Exponential::from_millis`] uses the initial delay in milliseconds as the factor,
+/// e.g 10, 100, 1000 milliseconds, while a strategy created from a [`Duration`] doubles the delay.
+/// [`Exponential::factor`] changes the factor of both.
+#[derive(Debug, Clone)]
+pub struct Exponential {
+    current: Duration,
+    factor: f64,
+}
+
+impl Exponential {
+    /// Waits `millis` milliseconds before the first retry and `millis` to the power of n before the nth retry
+    pub fn from_millis(millis: u64) -> Exponential {
+        Exponential { current: Duration::from_millis(millis), factor: millis as f64 }
+    }
+
+    /// Multiplies the delay by `factor` after every retry
+    pub fn factor(self, factor: f64) -> Exponential {
+        Exponential { factor, ..self }
+    }
+}
+
+/// Waits `current` before the first retry and doubles it after every retry
+impl From<Duration> for Exponential {
+    fn from(current: Duration) -> Exponential {
+        Exponential { current, factor: 2.0 }
+    }
+}
+
+impl Iterator for Exponential {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay = self.current;
+        self.current = Duration::try_from_secs_f64(delay.as_secs_f64() * self.factor).unwrap_or(Duration::MAX);
+        Some(delay)
+    }
+}
+
+/// Delays following the fibonacci sequence, e.g 10, 10, 20, 30, 50 milliseconds
+#[derive(Debug, Clone)]
+pub struct Fibonacci {
+    current: Duration,
+    next: Duration,
+}
+
+impl Fibonacci {
+    /// Waits `millis` milliseconds before the first and the second retry
+    pub fn from_millis(millis: u64) -> Fibonacci {
+        Fibonacci::from(Duration::from_millis(millis))
+    }
+}
+
+impl From<Duration> for Fibonacci {
+    fn from(delay: Duration) -> Fibonacci {
+        Fibonacci { current: delay, next: delay }
+    }
+}
+
+impl Iterator for Fibonacci {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay = self.current;
+        (self.current, self.next) = (self.next, self.current.saturating_add(self.next));
+        Some(delay)
+    }
+}
+
+/// Random delay between zero and `delay`, spreads the retries of concurrent callers e.g `Fixed::from_millis(100).map(jitter)`
+pub fn jitter(delay: Duration) -> Duration {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new(RandomState::new().hash_one(std::thread::current().id()) | 1);
+    }
+    //xorshift64, a fraction in [0, 1) from the upper 53 bits
+    let random = STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    });
+    delay.mul_f64(random)
+}
+
+/// Delays of a strategy until a retry would start after the maximum elapsed time, see [`Delays::max_elapsed`]
+#[derive(Debug, Clone)]
+pub struct MaxElapsed<I> {
+    delays: I,
+    max: Duration,
+    started: Instant,
+    delayed: Duration,
+}
+
+impl<I: Iterator<Item = Duration>> Iterator for MaxElapsed<I> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay = self.delays.next()?;
+        //the delays already taken count even if the clock did not advance, e.g with the manual clock
+        let elapsed = self.started.elapsed().max(self.delayed);
+        if elapsed.saturating_add(delay) > self.max {
+            return None;
+        }
+        self.delayed += delay;
+        Some(delay)
+    }
+}
+
+/// Adapters of delay strategies
+pub trait Delays: Iterator<Item = Duration> + Sized {
+    /// Stops retrying once the next retry would start more than `max` after the strategy was created
+    fn max_elapsed(self, max: Duration) -> MaxElapsed<Self> {
+        MaxElapsed { delays: self, max, started: Instant::now(), delayed: Duration::ZERO }
+    }
+}
+
+impl<I: Iterator<Item = Duration>> Delays for I {}
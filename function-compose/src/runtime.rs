@@ -0,0 +1,289 @@
+//! Timer and lock used by the async retry loop and the timeouts of composeable functions.
+//!
+//! The timer backend is selected by cargo features: `tokio`(default), `async-std` or `smol`.
+//! If several backends are enabled `tokio` is preferred, followed by `async-std` and `smol`.
+//! Without any backend the delays are awaited on a timer thread started on first use, so retry works with any executor.
+//! The `manual-clock` feature does not change the backend, a test opts in by installing a [`manual_clock::ManualClock`],
+//! which replaces the timer for the whole process until it is dropped.
+
+use std::future::Future;
+use std::pin::pin;
+use std::time::Duration;
+
+use futures::future::Either;
+
+use crate::TimedOut;
+
+/// Async mutex holding the exclusive reference arguments of a retried function between attempts
+pub use futures::lock::Mutex;
+
+/// Completes after `duration` has elapsed on the timer of the selected backend
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "manual-clock")]
+    if let Some(sleep) = manual_clock::sleep(duration) {
+        return sleep.await;
+    }
+    backend::sleep(duration).await
+}
+
+/// Completes with the output of `future`, or with [`TimedOut`] if `duration` elapses first
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, TimedOut> {
+    match futures::future::select(pin!(future), pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(TimedOut { duration }),
+    }
+}
+
+#[cfg(feature = "tokio")]
+mod backend {
+    pub(super) use tokio::time::sleep;
+}
+
+#[cfg(all(not(feature = "tokio"), feature = "async-std"))]
+mod backend {
+    pub(super) use async_std::task::sleep;
+}
+
+#[cfg(all(not(any(feature = "tokio", feature = "async-std")), feature = "smol"))]
+mod backend {
+    use std::time::Duration;
+
+    pub(super) async fn sleep(duration: Duration) {
+        smol::Timer::after(duration).await;
+    }
+}
+
+//a single timer thread wakes the sleeps in the order of their deadlines
+#[cfg(not(any(feature = "tokio", feature = "async-std", feature = "smol")))]
+mod backend {
+    use std::collections::BTreeMap;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Condvar, Mutex, MutexGuard, Once};
+    use std::task::{Context, Poll, Waker};
+    use std::time::{Duration, Instant};
+
+    struct Timers {
+        /// wakers of the pending sleeps by deadline and id
+        sleepers: BTreeMap<(Instant, u64), Waker>,
+        next_id: u64,
+    }
+
+    static TIMERS: Mutex<Timers> = Mutex::new(Timers { sleepers: BTreeMap::new(), next_id: 0 });
+    static CHANGED: Condvar = Condvar::new();
+    static TIMER_THREAD: Once = Once::new();
+
+    fn lock_timers() -> MutexGuard<'static, Timers> {
+        TIMERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn run_timers() {
+        let mut timers = lock_timers();
+        loop {
+            let now = Instant::now();
+            let pending = timers.sleepers.split_off(&(now, u64::MAX));
+            let elapsed = std::mem::replace(&mut timers.sleepers, pending);
+            if !elapsed.is_empty() {
+                drop(timers);
+                elapsed.into_values().for_each(Waker::wake);
+                timers = lock_timers();
+                continue;
+            }
+            timers = match timers.sleepers.first_key_value() {
+                Some(((deadline, _), _)) => {
+                    let timeout = *deadline - now;
+                    CHANGED.wait_timeout(timers, timeout).unwrap_or_else(|poisoned| poisoned.into_inner()).0
+                }
+                None => CHANGED.wait(timers).unwrap_or_else(|poisoned| poisoned.into_inner()),
+            };
+        }
+    }
+
+    pub(super) fn sleep(duration: Duration) -> Sleep {
+        let deadline = Instant::now().checked_add(duration);
+        Sleep { deadline, key: None }
+    }
+
+    pub(super) struct Sleep {
+        /// `None` for a duration too long to be represented, the sleep never completes
+        deadline: Option<Instant>,
+        key: Option<(Instant, u64)>,
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let Some(deadline) = self.deadline else {
+                return Poll::Pending;
+            };
+            if Instant::now() >= deadline {
+                return Poll::Ready(());
+            }
+            let mut timers = lock_timers();
+            let key = match self.key {
+                Some(key) => key,
+                None => {
+                    timers.next_id += 1;
+                    let key = (deadline, timers.next_id);
+                    self.key = Some(key);
+                    key
+                }
+            };
+            match timers.sleepers.get_mut(&key) {
+                Some(waker) => waker.clone_from(cx.waker()),
+                None => {
+                    timers.sleepers.insert(key, cx.waker().clone());
+                    drop(timers);
+                    TIMER_THREAD.call_once(|| {
+                        std::thread::Builder::new()
+                            .name("function-compose-timer".to_owned())
+                            .spawn(run_timers)
+                            .expect("failed to spawn the timer thread");
+                    });
+                    CHANGED.notify_one();
+                }
+            }
+            Poll::Pending
+        }
+    }
+
+    impl Drop for Sleep {
+        fn drop(&mut self) {
+            if let Some(key) = self.key {
+                lock_timers().sleepers.remove(&key);
+            }
+        }
+    }
+}
+
+/// Clock for tests of retried functions, delays elapse only when the clock is advanced.
+///
+/// The clock replaces the timer backend for the sleeps started on the thread which installed it, until it is dropped.
+/// Tests running the pipeline on their own thread, e.g with `#[tokio::test]`, don't affect each other or the rest of the process.
+///
+/// ```ignore
+/// let clock = ManualClock::install();
+/// let pipeline = tokio::spawn(compose!(fetch_quota -> with_args("ann")));
+/// while clock.pending() == 0 {
+///     tokio::task::yield_now().await;
+/// }
+/// clock.advance(Duration::from_millis(100));
+/// ```
+#[cfg(feature = "manual-clock")]
+pub mod manual_clock {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::task::{Context, Poll, Waker};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Clock {
+        now: Duration,
+        next_id: u64,
+        /// id, deadline and waker of the pending sleeps
+        sleepers: Vec<(u64, Duration, Waker)>,
+    }
+
+    /// Clock used by the sleeps of every thread while it is installed
+    static INSTALLED: Mutex<Option<Arc<Mutex<Clock>>>> = Mutex::new(None);
+    /// Notified when the installed clock is dropped
+    static UNINSTALLED: Condvar = Condvar::new();
+
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Clock installed for the whole process until it is dropped.
+    ///
+    /// Only one clock is installed at a time, so the tests using it run one after another even if the test harness runs them in parallel.
+    /// The clock can be advanced from any thread, the sleeps waiting for it are woken on the threads of their executors.
+    pub struct ManualClock {
+        clock: Arc<Mutex<Clock>>,
+    }
+
+    impl ManualClock {
+        /// Installs a clock starting at zero, the sleeps started on any thread wait for it to advance.
+        ///
+        /// Blocks while another clock is installed, installing a second clock before dropping the first on the same thread never returns.
+        pub fn install() -> ManualClock {
+            let clock = Arc::new(Mutex::new(Clock::default()));
+            let mut installed = lock(&INSTALLED);
+            while installed.is_some() {
+                installed = UNINSTALLED.wait(installed).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            *installed = Some(clock.clone());
+            ManualClock { clock }
+        }
+
+        /// Time elapsed on the clock
+        pub fn now(&self) -> Duration {
+            lock(&self.clock).now
+        }
+
+        /// Number of delays waiting for the clock to advance
+        pub fn pending(&self) -> usize {
+            lock(&self.clock).sleepers.len()
+        }
+
+        /// Advances the clock by `duration`, completing the delays which elapsed
+        pub fn advance(&self, duration: Duration) {
+            let mut clock = lock(&self.clock);
+            clock.now += duration;
+            let now = clock.now;
+            let (elapsed, pending) = clock.sleepers.drain(..).partition(|(_, deadline, _)| *deadline <= now);
+            clock.sleepers = pending;
+            drop(clock);
+            for (_, _, waker) in elapsed {
+                waker.wake();
+            }
+        }
+    }
+
+    impl Drop for ManualClock {
+        fn drop(&mut self) {
+            *lock(&INSTALLED) = None;
+            UNINSTALLED.notify_all();
+        }
+    }
+
+    /// Sleep on the installed clock, `None` if there is none
+    pub(crate) fn sleep(duration: Duration) -> Option<Sleep> {
+        let clock = lock(&INSTALLED).clone()?;
+        let mut locked = lock(&clock);
+        let (id, deadline) = (locked.next_id, locked.now + duration);
+        locked.next_id += 1;
+        drop(locked);
+        Some(Sleep { clock, id, deadline })
+    }
+
+    pub(crate) struct Sleep {
+        clock: Arc<Mutex<Clock>>,
+        id: u64,
+        deadline: Duration,
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let mut clock = lock(&self.clock);
+            if clock.now >= self.deadline {
+                return Poll::Ready(());
+            }
+            match clock.sleepers.iter_mut().find(|(id, _, _)| *id == self.id) {
+                Some((_, _, waker)) => waker.clone_from(cx.waker()),
+                None => clock.sleepers.push((self.id, self.deadline, cx.waker().clone())),
+            }
+            Poll::Pending
+        }
+    }
+
+    //a sleep dropped before its deadline, e.g the delay of a timeout whose future completed first, is no longer pending
+    impl Drop for Sleep {
+        fn drop(&mut self) {
+            lock(&self.clock).sleepers.retain(|(id, _, _)| *id != self.id);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Spreading the tuple returned by a function into the arguments of the next function.
+//!
+//! A function returning `(A, B)` can be composed with a function accepting `(a: A, b: B)` using the `->>` connector,
+//! e.g `compose!(generate_token ->> pack_auth_result -> with_args(auth_request))`.
+
+use std::sync::Arc;
+
+use paste::paste;
+
+use crate::*;
+
+/// Marks function F whose result(a tuple) is spread into the arguments of the function composed after it.
+///
+/// Created by the `->>` connector of the compose macros.
+#[derive(Clone, Copy)]
+pub struct Spread<F>(pub F);
+
+/// Converts a function with N arguments to a single argument function accepting the tuple of the N arguments
+pub trait IntoSpreadFn {
+    type Output;
+
+    fn into_spread_fn(self) -> Self::Output;
+}
+
+impl<'a, A, B, C, F, R, L> Then<'a, A, B, C, F, R> for Spread<L>
+where
+    F: IntoSpreadFn,
+    L: Then<'a, A, B, C, F::Output, R>,
+{
+    fn then(self, f: F) -> R {
+        self.0.then(f.into_spread_fn())
+    }
+}
+
+macro_rules! impl_into_spread_fn {
+    ([$($args:ident),*], $arg_size:literal) => {
+        paste!{
+            #[doc = concat!("Converts BoxedFn", stringify!($arg_size), " to BoxedFn1 accepting a tuple")]
+            impl<'a, $($args: 'a,)* R: 'a, E: 'a> IntoSpreadFn for [<BoxedFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = BoxedFn1<'a, ($($args,)*), R, E>;
+
+                fn into_spread_fn(self) -> Self::Output {
+                    Box::new(move |($([<$args:lower>],)*): ($($args,)*)| self($([<$args:lower>]),*))
+                }
+            }
+
+            #[doc = concat!("Converts BoxedAsyncFn", stringify!($arg_size), " to BoxedAsyncFn1 accepting a tuple")]
+            impl<'a, $($args: 'a,)* R: 'a, E: 'a> IntoSpreadFn for [<BoxedAsyncFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = BoxedAsyncFn1<'a, ($($args,)*), R, E>;
+
+                fn into_spread_fn(self) -> Self::Output {
+                    Box::new(move |($([<$args:lower>],)*): ($($args,)*)| self($([<$args:lower>]),*))
+                }
+            }
+
+            #[doc = concat!("Converts SharedFn", stringify!($arg_size), " to SharedFn1 accepting a tuple")]
+            impl<'a, $($args: 'a,)* R: 'a, E: 'a> IntoSpreadFn for [<SharedFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = SharedFn1<'a, ($($args,)*), R, E>;
+
+                fn into_spread_fn(self) -> Self::Output {
+                    Arc::new(move |($([<$args:lower>],)*): ($($args,)*)| self($([<$args:lower>]),*))
+                }
+            }
+
+            #[doc = concat!("Converts SharedAsyncFn", stringify!($arg_size), " to SharedAsyncFn1 accepting a tuple")]
+            impl<'a, $($args: 'a,)* R: 'a, E: 'a> IntoSpreadFn for [<SharedAsyncFn $arg_size>]<'a, $($args,)* R, E> {
+                type Output = SharedAsyncFn1<'a, ($($args,)*), R, E>;
+
+                fn into_spread_fn(self) -> Self::Output {
+                    Arc::new(move |($([<$args:lower>],)*): ($($args,)*)| self($([<$args:lower>]),*))
+                }
+            }
+        }
+    };
+}
+
+impl_into_spread_fn!([T1], 1);
+impl_into_spread_fn!([T1, T2], 2);
+impl_into_spread_fn!([T1, T2, T3], 3);
+impl_into_spread_fn!([T1, T2, T3, T4], 4);
+impl_into_spread_fn!([T1, T2, T3, T4, T5], 5);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6], 6);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7], 7);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8], 8);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9], 9);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], 10);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], 11);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], 12);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], 13);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], 14);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], 15);
+impl_into_spread_fn!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], 16);
+
+/// Step composing F with G, where the tuple returned by F is the tuple of arguments of G
+impl<F, G> Step for Composed<Spread<F>, G>
+where
+    F: Step,
+    G: Step<Input = F::Output>,
+    G::Error: From<F::Error>,
+{
+    type Input = F::Input;
+    type Output = G::Output;
+    type Error = G::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        match self.first.0.call(input).await {
+            Ok(inner_result) => self.second.call(inner_result).await,
+            Err(error) => Err(crate::to_fn_error(error)),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Statically typed composition.
+//!
+//! Unlike the boxed `BoxedFn*`/`BoxedAsyncFn*` types, steps built from [`SyncStep`], [`AsyncStep`], [`Provide`] and [`Composed`]
+//! keep the concrete type of every function in the pipeline. The pipeline is monomorphised by the compiler and
+//! composing does not allocate. Use [`compose_static!`](crate::compose_static) to build such a pipeline.
+//!
+//! Arguments of a step are passed as a tuple, `(a,)` for single argument functions.
+
+use std::future::Future;
+use std::marker::PhantomData;
+
+/// A single step of a statically typed pipeline.
+///
+/// Input is the tuple of arguments accepted by the step, Output and Error are the Ok and Err types of the step result.
+pub trait Step {
+    type Input;
+    type Output;
+    type Error;
+
+    /// Runs the step with the given arguments. Sync steps return an already completed future.
+    fn call(self, input: Self::Input) -> impl Future<Output = Result<Self::Output, Self::Error>>;
+}
+
+/// Splits a tuple into all but the last element(Init) and the last element(Last).
+///
+/// Used by [`Provide`] to inject the last argument of a step.
+pub trait SplitLast {
+    type Init;
+    type Last;
+
+    /// Builds the tuple back from its init and last element
+    fn join(init: Self::Init, last: Self::Last) -> Self;
+}
+
+macro_rules! impl_split_last {
+    ([$($args:ident),*], $last:ident) => {
+        paste::paste!{
+            impl<$($args,)* $last> SplitLast for ($($args,)* $last,) {
+                type Init = ($($args,)*);
+                type Last = $last;
+
+                #[allow(clippy::unused_unit)]
+                fn join(init: Self::Init, last: Self::Last) -> Self {
+                    let ($([<$args:lower>],)*) = init;
+                    ($([<$args:lower>],)* last,)
+                }
+            }
+        }
+    };
+}
+
+impl_split_last!([], T1);
+impl_split_last!([T1], T2);
+impl_split_last!([T1, T2], T3);
+impl_split_last!([T1, T2, T3], T4);
+impl_split_last!([T1, T2, T3, T4], T5);
+impl_split_last!([T1, T2, T3, T4, T5], T6);
+impl_split_last!([T1, T2, T3, T4, T5, T6], T7);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7], T8);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8], T9);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15);
+impl_split_last!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16);
+
+/// Step wrapping a sync function. Type param Args is the tuple of function arguments.
+pub struct SyncStep<F, Args> {
+    f: F,
+    _args: PhantomData<fn(Args)>,
+}
+
+/// Step wrapping an async function, i.e function returning any Future. Type param Args is the tuple of function arguments.
+pub struct AsyncStep<F, Args> {
+    f: F,
+    _args: PhantomData<fn(Args)>,
+}
+
+impl<F, Args> SyncStep<F, Args> {
+    pub fn new(f: F) -> Self {
+        SyncStep { f, _args: PhantomData }
+    }
+}
+
+impl<F, Args> AsyncStep<F, Args> {
+    pub fn new(f: F) -> Self {
+        AsyncStep { f, _args: PhantomData }
+    }
+}
+
+impl<F: Clone, Args> Clone for SyncStep<F, Args> {
+    fn clone(&self) -> Self {
+        SyncStep::new(self.f.clone())
+    }
+}
+
+impl<F: Copy, Args> Copy for SyncStep<F, Args> {}
+
+impl<F: Clone, Args> Clone for AsyncStep<F, Args> {
+    fn clone(&self) -> Self {
+        AsyncStep::new(self.f.clone())
+    }
+}
+
+impl<F: Copy, Args> Copy for AsyncStep<F, Args> {}
+
+macro_rules! impl_fn_step {
+    ([$($args:ident),*], $return_type:ident, $error_type:ident) => {
+        paste::paste!{
+            #[doc = concat!("Step implementation for sync function with ", stringify!($($args),*), " arguments")]
+            impl<F, $($args,)* $return_type, $error_type> Step for SyncStep<F, ($($args,)*)>
+            where F: FnOnce($($args),*) -> Result<$return_type, $error_type>
+            {
+                type Input = ($($args,)*);
+                type Output = $return_type;
+                type Error = $error_type;
+
+                fn call(self, input: Self::Input) -> impl Future<Output = Result<Self::Output, Self::Error>> {
+                    let ($([<$args:lower>],)*) = input;
+                    std::future::ready((self.f)($([<$args:lower>]),*))
+                }
+            }
+
+            #[doc = concat!("Step implementation for async function with ", stringify!($($args),*), " arguments")]
+            impl<F, Fut, $($args,)* $return_type, $error_type> Step for AsyncStep<F, ($($args,)*)>
+            where F: FnOnce($($args),*) -> Fut, Fut: Future<Output = Result<$return_type, $error_type>>
+            {
+                type Input = ($($args,)*);
+                type Output = $return_type;
+                type Error = $error_type;
+
+                fn call(self, input: Self::Input) -> impl Future<Output = Result<Self::Output, Self::Error>> {
+                    let ($([<$args:lower>],)*) = input;
+                    (self.f)($([<$args:lower>]),*)
+                }
+            }
+        }
+    };
+}
+
+impl_fn_step!([T1], T2, E1);
+impl_fn_step!([T1, T2], T3, E1);
+impl_fn_step!([T1, T2, T3], T4, E1);
+impl_fn_step!([T1, T2, T3, T4], T5, E1);
+impl_fn_step!([T1, T2, T3, T4, T5], T6, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6], T7, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7], T8, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8], T9, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13], T14, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14], T15, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15], T16, E1);
+impl_fn_step!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16], T17, E1);
+
+/// Step injecting the last argument of the underlying step, the statically typed counterpart of [`Injector`](crate::Injector).
+#[derive(Clone, Copy)]
+pub struct Provide<S, P> {
+    step: S,
+    provided: P,
+}
+
+impl<S> Provide<S, <S::Input as SplitLast>::Last>
+where
+    S: Step,
+    S::Input: SplitLast,
+{
+    pub fn new(step: S, provided: <S::Input as SplitLast>::Last) -> Self {
+        Provide { step, provided }
+    }
+}
+
+impl<S, P> Step for Provide<S, P>
+where
+    S: Step,
+    S::Input: SplitLast<Last = P>,
+{
+    type Input = <S::Input as SplitLast>::Init;
+    type Output = S::Output;
+    type Error = S::Error;
+
+    fn call(self, input: Self::Input) -> impl Future<Output = Result<Self::Output, Self::Error>> {
+        self.step.call(SplitLast::join(input, self.provided))
+    }
+}
+
+/// Step running step F and passing its result to step G.
+///
+/// The error of F is converted to the error type of G using From, same as [`Then`](crate::Then)
+#[derive(Clone, Copy)]
+pub struct Composed<F, G> {
+    pub(crate) first: F,
+    pub(crate) second: G,
+}
+
+impl<F, G> Composed<F, G> {
+    pub fn new(first: F, second: G) -> Self {
+        Composed { first, second }
+    }
+}
+
+impl<F, G> Step for Composed<F, G>
+where
+    F: Step,
+    G: Step<Input = (F::Output,)>,
+    G::Error: From<F::Error>,
+{
+    type Input = F::Input;
+    type Output = G::Output;
+    type Error = G::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        match self.first.call(input).await {
+            Ok(inner_result) => self.second.call((inner_result,)).await,
+            Err(error) => Err(crate::to_fn_error(error)),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Time limits of async functions.
+//!
+//! [`Timeout`] limits the time of the async functions composed so far, `#[composeable(timeout_ms = 500)]` limits every call
+//! of a single function. A function which does not complete in time fails with [`TimedOut`] converted into its error type,
+//! so the error type must implement `From<TimedOut>`.
+
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::FutureExt;
+
+use crate::{BoxedAsyncFn1, SharedAsyncFn1, Step};
+
+/// Error of a function which did not complete within its time limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut {
+    /// the time limit which elapsed
+    pub duration: Duration,
+}
+
+impl Display for TimedOut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out after {:?}", self.duration)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Awaits `future` for at most `duration`, the elapsed limit is returned as the error type of the future
+pub async fn with_timeout<T, E, F>(duration: Duration, future: F) -> Result<T, E>
+where
+    E: From<TimedOut>,
+    F: Future<Output = Result<T, E>>,
+{
+    crate::runtime::timeout(duration, future).await.unwrap_or_else(|timed_out| Err(From::from(timed_out)))
+}
+
+/// Fails the function with [`TimedOut`] when it does not complete within the duration.
+///
+/// Only async functions can be interrupted, sync functions always run to completion.
+#[diagnostic::on_unimplemented(
+    message = "timeout can be applied only to async functions whose error type implements From<TimedOut>, found `{Self}`",
+    note = "a sync function cannot be interrupted"
+)]
+pub trait Timeout {
+    fn timeout(self, duration: Duration) -> Self;
+}
+
+impl<'a, A: 'a, B: Send + 'a, E: From<TimedOut> + Send + 'a> Timeout for BoxedAsyncFn1<'a, A, B, E> {
+    fn timeout(self, duration: Duration) -> Self {
+        Box::new(move |a: A| with_timeout(duration, self(a)).boxed())
+    }
+}
+
+impl<'a, A: 'a, B: Send + 'a, E: From<TimedOut> + Send + 'a> Timeout for SharedAsyncFn1<'a, A, B, E> {
+    fn timeout(self, duration: Duration) -> Self {
+        Arc::new(move |a: A| with_timeout(duration, self(a)).boxed())
+    }
+}
+
+/// Step failing with [`TimedOut`] when the underlying step does not complete within the duration, the statically typed counterpart of [`Timeout`]
+#[derive(Clone, Copy)]
+pub struct TimeLimited<S> {
+    step: S,
+    duration: Duration,
+}
+
+impl<S: Step> TimeLimited<S> {
+    pub fn new(step: S, duration: Duration) -> Self {
+        TimeLimited { step, duration }
+    }
+}
+
+impl<S> Step for TimeLimited<S>
+where
+    S: Step,
+    S::Error: From<TimedOut>,
+{
+    type Input = S::Input;
+    type Output = S::Output;
+    type Error = S::Error;
+
+    async fn call(self, input: Self::Input) -> Result<Self::Output, Self::Error> {
+        with_timeout(self.duration, self.step.call(input)).await
+    }
+}
@@ -0,0 +1,80 @@
+//! Retry delays on the manual clock, run with `cargo test -p function-compose --features manual-clock --test manual_clock`
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+use function_compose::runtime;
+use function_compose::runtime::manual_clock::ManualClock;
+use function_compose::*;
+use function_compose::retry::*;
+
+static FETCH_ATTEMPTS: AtomicI32 = AtomicI32::new(0);
+static REFRESH_ATTEMPTS: AtomicI32 = AtomicI32::new(0);
+
+#[composeable(retry = Fixed::from_millis(100).take(3))]
+pub async fn fetch_quota(user: &str) -> Result<i32, String> {
+    if FETCH_ATTEMPTS.fetch_add(1, Ordering::SeqCst) < 2 {
+        return Err(format!("quota of {user} unavailable"));
+    }
+    Ok(10)
+}
+
+#[composeable(retry = Fixed::from_millis(50).take(2))]
+pub async fn refresh_quota(user: &str) -> Result<i32, String> {
+    if REFRESH_ATTEMPTS.fetch_add(1, Ordering::SeqCst) < 1 {
+        return Err(format!("quota of {user} unavailable"));
+    }
+    Ok(5)
+}
+
+#[composeable()]
+pub fn double(quota: i32) -> Result<i32, String> {
+    Ok(quota * 2)
+}
+
+#[tokio::test]
+async fn test_retry_delays_elapse_on_manual_clock() {
+    let clock = ManualClock::install();
+    let pipeline = tokio::spawn(compose!(fetch_quota -> double -> with_args("ann")));
+    while !pipeline.is_finished() {
+        if clock.pending() == 0 {
+            tokio::task::yield_now().await;
+        } else {
+            clock.advance(Duration::from_millis(100));
+        }
+    }
+    assert_eq!(20, pipeline.await.unwrap().unwrap());
+    assert_eq!(3, FETCH_ATTEMPTS.load(Ordering::SeqCst));
+    assert_eq!(Duration::from_millis(200), clock.now());
+}
+
+#[tokio::test]
+async fn test_timeout_completed_before_deadline_leaves_no_pending_delay() {
+    let clock = ManualClock::install();
+    let doubled = runtime::timeout(Duration::from_secs(1), async {
+        tokio::task::yield_now().await;
+        double(5)
+    });
+    assert_eq!(Ok(Ok(10)), doubled.await);
+    assert_eq!(0, clock.pending());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_manual_clock_advanced_from_another_thread() {
+    let clock = Arc::new(ManualClock::install());
+    let pipeline = tokio::spawn(compose!(refresh_quota -> double -> with_args("bob")));
+    let advancing = {
+        let clock = clock.clone();
+        std::thread::spawn(move || {
+            while clock.pending() == 0 {
+                std::thread::yield_now();
+            }
+            clock.advance(Duration::from_millis(50));
+        })
+    };
+    assert_eq!(10, pipeline.await.unwrap().unwrap());
+    advancing.join().unwrap();
+    assert_eq!(2, REFRESH_ATTEMPTS.load(Ordering::SeqCst));
+    assert_eq!(Duration::from_millis(50), clock.now());
+}
@@ -2,7 +2,7 @@
 
 use function_compose::*;
 use futures::{future::BoxFuture, FutureExt};
-use retry::delay::*;
+use function_compose::retry::*;
 
 static mut RETRY_COUNT: i32 = 0;
 
@@ -323,4 +323,1144 @@ fn retry_count_lessthan(count:i32) -> bool{
     unsafe{
         RETRY_COUNT < count
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_compose_shared_sync_functions() {
+    let composed = compose_shared!(add_10 -> add_100);
+    assert_eq!(120, composed(10).unwrap());
+    let cloned = composed.clone();
+    assert_eq!(130, cloned(20).unwrap());
+    let result = compose_shared!(add_10 -> add_100 -> with_args(10));
+    assert_eq!(120, result.unwrap());
+}
+
+#[tokio::test]
+async fn test_compose_shared_async_functions() {
+    let composed = compose_shared!(add_async.provide(100) -> add_100 -> add_3_arg_async.provide(1).provide(1));
+    assert_eq!(212, composed(10).await.unwrap());
+    assert_eq!(222, composed(20).await.unwrap());
+
+    let handles: Vec<_> = (0..4).map(|i| {
+        let composed = composed.clone();
+        tokio::spawn(async move { composed(i).await.unwrap() })
+    }).collect();
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(202 + i as i32, handle.await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_compose_static_functions() {
+    let result = compose_static!(add_10 -> add_100 -> with_args(10)).await;
+    assert_eq!(120, result.unwrap());
+
+    let result = compose_static!(add_100_async -> add_100 -> add_async.provide(1) -> with_args(10)).await;
+    assert_eq!(211, result.unwrap());
+
+    let result: Result<i32, FnError<String>> =
+        compose_static!(add_3_arg_async.provide(1).provide(1) -> add_10 -> with_args(10)).await;
+    assert_eq!(22, result.unwrap());
+
+    let result = compose_static!(f1 -> f2 -> with_args(10)).await;
+    assert_eq!(0, result.unwrap());
+
+    let pipeline = compose_static!(add_10 -> add_async.provide(100) -> add_100);
+    assert_eq!(220, pipeline.call((10,)).await.unwrap());
+    let handle = tokio::spawn(async move { pipeline.call((20,)).await.unwrap() });
+    assert_eq!(230, handle.await.unwrap());
+}
+
+#[tokio::test]
+async fn test_compose_static_retry() {
+    let v1 = Box::leak(Box::new(vec!["1".to_owned()]));
+    let v2 = Box::leak(Box::new(vec!["1".to_owned()]));
+    let v3 = Box::leak(Box::new(vec!["1".to_owned()]));
+    let result = compose_static!(add_vec_size_ref_non_copy_sync.provide(v1).provide(v2) -> add_100 -> with_args(v3)).await;
+    assert_eq!(103, result.unwrap());
+}
+
+#[composeable()]
+pub async fn add_1000_native_async(a: i32) -> Result<i32, FnError<String>> {
+    Ok(a + 1000)
+}
+
+#[composeable()]
+pub fn add_impl_future(a: i32, b: i32) -> impl std::future::Future<Output = Result<i32, FnError<String>>> {
+    std::future::ready(Ok(a + b))
+}
+
+#[composeable()]
+pub fn add_1_pinned_future(
+    a: i32,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<i32, FnError<String>>> + Send>> {
+    Box::pin(async move { Ok(a + 1) })
+}
+
+#[composeable()]
+pub fn add_2_qualified_box_future(a: i32) -> futures::future::BoxFuture<'static, Result<i32, FnError<String>>> {
+    async move { Ok(a + 2) }.boxed()
+}
+
+#[composeable(retry = Fixed::from_millis(10).take(2))]
+pub async fn add_ref_native_async(a: &i32, b: &i32) -> Result<i32, FnError<String>> {
+    if NATIVE_RETRY_COUNT.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+        return Err(FnError {
+            description: Some("Retry test".to_owned()),
+            error_code: None,
+            underlying_error: None,
+        });
+    }
+    Ok(a + b)
+}
+
+static NATIVE_RETRY_COUNT: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[tokio::test]
+async fn test_compose_native_async_functions() {
+    let result = compose!(add_1000_native_async -> add_impl_future.provide(1) -> add_1_pinned_future -> add_2_qualified_box_future -> with_args(10)).await;
+    assert_eq!(1014, result.unwrap());
+
+    let result = compose!(add_10 -> add_1000_native_async -> add_100 -> with_args(10)).await;
+    assert_eq!(1120, result.unwrap());
+
+    let pipeline = compose_shared!(add_1000_native_async -> add_impl_future.provide(1));
+    assert_eq!(1011, pipeline(10).await.unwrap());
+    assert_eq!(1021, pipeline(20).await.unwrap());
+
+    let result = compose_static!(add_1000_native_async -> add_impl_future.provide(1) -> add_1_pinned_future -> with_args(10)).await;
+    assert_eq!(1012, result.unwrap());
+}
+
+#[tokio::test]
+async fn test_compose_native_async_retry() {
+    let one = &1;
+    let result = compose!(add_ref_native_async.provide(one) -> add_100 -> with_args(&10)).await;
+    assert_eq!(111, result.unwrap());
+    assert_eq!(3, NATIVE_RETRY_COUNT.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+static QUOTA_CALLS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+static ASYNC_QUOTA_CALLS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+fn quota_error(code: &str) -> FnError<String> {
+    FnError { underlying_error: None, error_code: Some(code.to_owned()), description: None }
+}
+
+fn is_transient(error: &FnError<String>) -> bool {
+    error.error_code.as_deref() == Some("transient")
+}
+
+/// Fails permanently for user 0, other users fail with a transient error on the first two calls
+fn quota(user: i32, calls: &std::sync::atomic::AtomicI32) -> Result<i32, FnError<String>> {
+    let previous_calls = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    match user {
+        0 => Err(quota_error("denied")),
+        _ if previous_calls < 2 => Err(quota_error("transient")),
+        _ => Ok(user * 100),
+    }
+}
+
+#[composeable(retry = Fixed::from_millis(10).take(3), retry_if = is_transient)]
+pub fn lookup_quota(user: i32) -> Result<i32, FnError<String>> {
+    quota(user, &QUOTA_CALLS)
+}
+
+#[composeable(retry = Fixed::from_millis(10).take(3), retry_if = |error: &FnError<String>| error.error_code.as_deref() == Some("transient"))]
+pub async fn lookup_quota_async(user: i32) -> Result<i32, FnError<String>> {
+    quota(user, &ASYNC_QUOTA_CALLS)
+}
+
+#[tokio::test]
+async fn test_compose_conditional_retry() {
+    use std::sync::atomic::Ordering::SeqCst;
+    let result = compose!(lookup_quota -> add_10 -> with_args(0));
+    assert_eq!(Some("denied"), result.unwrap_err().error_code.as_deref());
+    assert_eq!(1, QUOTA_CALLS.swap(0, SeqCst));
+    let result = compose!(lookup_quota -> add_10 -> with_args(1));
+    assert_eq!(110, result.unwrap());
+    assert_eq!(3, QUOTA_CALLS.load(SeqCst));
+
+    let result = compose!(add_10 -> lookup_quota_async -> with_args(-10)).await;
+    assert_eq!(Some("denied"), result.unwrap_err().error_code.as_deref());
+    assert_eq!(1, ASYNC_QUOTA_CALLS.swap(0, SeqCst));
+    let result = compose!(add_10 -> lookup_quota_async -> with_args(-8)).await;
+    assert_eq!(200, result.unwrap());
+    assert_eq!(3, ASYNC_QUOTA_CALLS.load(SeqCst));
+}
+
+static BUSY_RETRIES: std::sync::Mutex<Vec<(usize, std::time::Duration, Option<String>)>> = std::sync::Mutex::new(Vec::new());
+static BUSY_ASYNC_RETRIES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+impl From<RetryExhausted<FnError<String>>> for FnError<String> {
+    fn from(exhausted: RetryExhausted<FnError<String>>) -> Self {
+        FnError {
+            underlying_error: exhausted.last_error.underlying_error,
+            error_code: Some("exhausted".to_owned()),
+            description: Some(format!("failed after {} attempts", exhausted.attempts)),
+        }
+    }
+}
+
+fn record_busy_retry(attempt: usize, delay: std::time::Duration, error: &FnError<String>) {
+    BUSY_RETRIES.lock().unwrap().push((attempt, delay, error.error_code.clone()));
+}
+
+#[composeable(retry = Fixed::from_millis(10).take(2), on_retry = record_busy_retry, report_exhausted)]
+pub fn always_busy(_a: i32) -> Result<i32, FnError<String>> {
+    Err(quota_error("transient"))
+}
+
+#[composeable(retry = Fixed::from_millis(10).take(3), retry_if = is_transient, on_retry = |_, _, _| { BUSY_ASYNC_RETRIES.fetch_add(1, std::sync::atomic::Ordering::SeqCst); }, report_exhausted)]
+pub async fn always_busy_async(user: i32) -> Result<i32, FnError<String>> {
+    match user {
+        0 => Err(quota_error("denied")),
+        _ => Err(quota_error("transient")),
+    }
+}
+
+#[tokio::test]
+async fn test_compose_retry_hooks() {
+    let error = compose!(add_10 -> always_busy -> with_args(1)).unwrap_err();
+    assert_eq!(Some("exhausted"), error.error_code.as_deref());
+    assert_eq!(Some("failed after 3 attempts"), error.description.as_deref());
+    let delay = std::time::Duration::from_millis(10);
+    let transient = Some("transient".to_owned());
+    assert_eq!(vec![(1, delay, transient.clone()), (2, delay, transient)], *BUSY_RETRIES.lock().unwrap());
+
+    let error = compose!(always_busy_async -> add_10 -> with_args(0)).await.unwrap_err();
+    assert_eq!(Some("denied"), error.error_code.as_deref());
+    assert_eq!(0, BUSY_ASYNC_RETRIES.load(std::sync::atomic::Ordering::SeqCst));
+    let error = compose!(always_busy_async -> add_10 -> with_args(1)).await.unwrap_err();
+    assert_eq!(Some("failed after 4 attempts"), error.description.as_deref());
+    assert_eq!(3, BUSY_ASYNC_RETRIES.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignupRequest {
+    pub email: String,
+}
+
+static SIGNUP_ATTEMPTS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+static NORMALIZE_ATTEMPTS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[composeable(retry = Fixed::from_millis(10).take(1))]
+pub fn normalize_signup(request: SignupRequest) -> Result<SignupRequest, FnError<String>> {
+    if NORMALIZE_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 1 {
+        return Err(quota_error("transient"));
+    }
+    Ok(SignupRequest { email: request.email.to_lowercase() })
+}
+
+#[composeable(retry = Fixed::from_millis(10).take(2))]
+pub async fn save_signup(request: SignupRequest, saved: &mut Vec<String>, tags: Vec<String>) -> Result<usize, FnError<String>> {
+    if SIGNUP_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+        return Err(quota_error("transient"));
+    }
+    saved.push(format!("{} {}", request.email, tags.join(",")));
+    Ok(saved.len())
+}
+
+static CONFIRM_ATTEMPTS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[composeable(retry = Fixed::from_millis(10).take(1))]
+pub fn confirm_signup<'a>(request: SignupRequest) -> BoxFuture<'a, Result<String, FnError<String>>> {
+    async move {
+        if CONFIRM_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 1 {
+            return Err(quota_error("transient"));
+        }
+        Ok(format!("confirmed {}", request.email))
+    }
+    .boxed()
+}
+
+#[tokio::test]
+async fn test_compose_retry_owned_arguments() {
+    let mut saved = vec![];
+    let tags = vec!["beta".to_owned()];
+    let request = SignupRequest { email: "Ann@Example.com".to_owned() };
+    let result = compose!(normalize_signup -> save_signup.provide(tags).provide(&mut saved) -> with_args(request)).await;
+    assert_eq!(1, result.unwrap());
+    assert_eq!(vec!["ann@example.com beta".to_owned()], saved);
+    assert_eq!(2, NORMALIZE_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst));
+    assert_eq!(3, SIGNUP_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst));
+
+    let request = SignupRequest { email: "ann@example.com".to_owned() };
+    let result = compose!(confirm_signup -> with_args(request)).await;
+    assert_eq!("confirmed ann@example.com", result.unwrap());
+    assert_eq!(2, CONFIRM_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+impl From<TimedOut> for FnError<String> {
+    fn from(timed_out: TimedOut) -> Self {
+        FnError { underlying_error: None, error_code: Some("timeout".to_owned()), description: Some(timed_out.to_string()) }
+    }
+}
+
+static SLOW_LOOKUP_ATTEMPTS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+static STALLED_ATTEMPTS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[composeable()]
+pub async fn lookup_after(delay_ms: u64) -> Result<u64, FnError<String>> {
+    tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+    Ok(delay_ms)
+}
+
+#[composeable(timeout_ms = 50)]
+pub fn lookup_within_limit(delay_ms: u64) -> BoxFuture<'static, Result<u64, FnError<String>>> {
+    lookup_after(delay_ms).boxed()
+}
+
+#[composeable(retry = Fixed::from_millis(1).take(2), timeout_ms = 50)]
+pub async fn slow_first_lookup(key: &str) -> Result<String, FnError<String>> {
+    if SLOW_LOOKUP_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
+    Ok(key.to_uppercase())
+}
+
+#[composeable(retry = Fixed::from_millis(20), deadline_ms = 100)]
+pub async fn stalled_lookup(key: &str) -> Result<String, FnError<String>> {
+    STALLED_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    Err(FnError::from(format!("{key} unavailable")))
+}
+
+#[tokio::test]
+async fn test_compose_timeout() {
+    let timed_out = |result: Result<u64, FnError<String>>| result.unwrap_err().error_code == Some("timeout".to_owned());
+    assert_eq!(10, compose!(lookup_within_limit -> with_args(10)).await.unwrap());
+    assert!(timed_out(compose!(lookup_within_limit -> with_args(5000)).await));
+
+    let limit = std::time::Duration::from_millis(50);
+    assert!(timed_out(compose!(lookup_after -> timeout(limit) -> with_args(5000)).await));
+    let pipeline = compose_shared!(lookup_after -> timeout(limit));
+    assert_eq!(10, pipeline(10).await.unwrap());
+    assert!(timed_out(pipeline(5000).await));
+    let pipeline = compose_static!(lookup_after -> timeout(limit));
+    assert!(timed_out(pipeline.call((5000,)).await));
+}
+
+#[tokio::test]
+async fn test_compose_timeout_with_retry() {
+    //the first attempt times out and is retried
+    assert_eq!("QUOTA", compose!(slow_first_lookup -> with_args("quota")).await.unwrap());
+    assert_eq!(2, SLOW_LOOKUP_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst));
+
+    //the deadline ends retrying without delay limit
+    let error = compose!(stalled_lookup -> with_args("quota")).await.unwrap_err();
+    assert_eq!(Some("timeout".to_owned()), error.error_code);
+    let attempts = STALLED_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst);
+    assert!((2..=6).contains(&attempts), "{attempts} attempts");
+}
+
+impl From<CircuitOpen> for FnError<String> {
+    fn from(open: CircuitOpen) -> Self {
+        FnError { underlying_error: None, error_code: Some("circuit_open".to_owned()), description: Some(open.to_string()) }
+    }
+}
+
+static STOCK_CALLS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+static PRICE_ATTEMPTS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+static PRICE_SERVICE_UP: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[composeable(circuit_breaker = "stock")]
+pub fn check_stock(quantity: i32) -> Result<i32, FnError<String>> {
+    STOCK_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    if quantity < 0 {
+        return Err(FnError::from("stock unavailable".to_owned()));
+    }
+    Ok(quantity)
+}
+
+#[composeable(retry = Fixed::from_millis(1).take(2), circuit_breaker = "prices")]
+pub async fn fetch_price(quantity: i32) -> Result<i32, FnError<String>> {
+    PRICE_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    if !PRICE_SERVICE_UP.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err(FnError::from("price service unavailable".to_owned()));
+    }
+    Ok(quantity * 10)
+}
+
+static SEAT_ATTEMPTS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[composeable(circuit_breaker = "inventory", breaker_if = is_transient)]
+pub fn count_inventory(item: i32) -> Result<i32, FnError<String>> {
+    match item {
+        0 => Err(quota_error("transient")),
+        _ if item < 0 => Err(quota_error("unknown_item")),
+        _ => Ok(item),
+    }
+}
+
+/// Negative seats don't exist, other seats are unavailable
+#[composeable(retry = Fixed::from_millis(1).take(2), retry_if = is_transient, circuit_breaker = "seats")]
+pub async fn reserve_seat(seat: i32) -> Result<i32, FnError<String>> {
+    SEAT_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    if seat < 0 {
+        return Err(quota_error("unknown_seat"));
+    }
+    Err(quota_error("transient"))
+}
+
+fn breaker_config(cool_down_ms: u64) -> CircuitBreakerConfig {
+    CircuitBreakerConfig { failure_ratio: 0.5, window: 4, minimum_calls: 2, cool_down: std::time::Duration::from_millis(cool_down_ms) }
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let breaker = CircuitBreaker::configure("stock", breaker_config(50)).unwrap();
+    let circuit_open = |result: Result<i32, FnError<String>>| result.unwrap_err().error_code == Some("circuit_open".to_owned());
+    assert_eq!(1, compose!(check_stock -> with_args(1)).unwrap());
+    assert!(compose!(check_stock -> with_args(-1)).is_err());
+    assert_eq!(CircuitState::Open, breaker.state());
+
+    //the open breaker fails without calling the function
+    assert!(circuit_open(compose!(check_stock -> with_args(2))));
+    assert_eq!(2, STOCK_CALLS.load(std::sync::atomic::Ordering::SeqCst));
+
+    //a failed trial call opens the breaker again, a successful one closes it
+    std::thread::sleep(std::time::Duration::from_millis(60));
+    assert_eq!(CircuitState::HalfOpen, breaker.state());
+    assert!(!circuit_open(compose!(check_stock -> with_args(-1))));
+    assert_eq!(CircuitState::Open, breaker.state());
+    std::thread::sleep(std::time::Duration::from_millis(60));
+    assert_eq!(3, compose!(check_stock -> with_args(3)).unwrap());
+    assert_eq!(CircuitState::Closed, breaker.state());
+}
+
+#[test]
+fn test_circuit_breaker_minimum_calls_above_window() {
+    let Err(error) = CircuitBreaker::configure("unreachable", CircuitBreakerConfig { minimum_calls: 5, ..breaker_config(50) }) else {
+        panic!("minimum_calls above the window accepted");
+    };
+    assert_eq!("minimum_calls of circuit breaker unreachable must be at least 1 and at most the window 4, found 5", error.to_string());
+}
+
+#[test]
+fn test_circuit_breaker_zero_failure_ratio() {
+    let Err(error) = CircuitBreaker::configure("always_open", CircuitBreakerConfig { failure_ratio: 0.0, ..breaker_config(50) }) else {
+        panic!("zero failure_ratio accepted");
+    };
+    assert_eq!(CircuitBreakerConfigError::FailureRatio { name: "always_open".to_owned(), failure_ratio: 0.0 }, error);
+}
+
+#[test]
+fn test_circuit_breaker_if() {
+    let breaker = CircuitBreaker::configure("inventory", breaker_config(60_000)).unwrap();
+    //errors breaker_if does not hold for are answers of a working dependency
+    assert!(compose!(count_inventory -> with_args(-1)).is_err());
+    assert!(compose!(count_inventory -> with_args(-2)).is_err());
+    assert_eq!(CircuitState::Closed, breaker.state());
+    assert!(compose!(count_inventory -> with_args(0)).is_err());
+    assert!(compose!(count_inventory -> with_args(0)).is_err());
+    assert_eq!(CircuitState::Open, breaker.state());
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_counts_exhausted_retries() {
+    let breaker = CircuitBreaker::configure("seats", breaker_config(60_000)).unwrap();
+    //errors which are not retried don't open the breaker
+    for _ in 0..3 {
+        assert_eq!(Some("unknown_seat".to_owned()), compose!(reserve_seat -> with_args(-1)).await.unwrap_err().error_code);
+    }
+    assert_eq!(CircuitState::Closed, breaker.state());
+    assert_eq!(3, SEAT_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst));
+
+    assert!(compose!(reserve_seat -> with_args(1)).await.is_err());
+    assert!(compose!(reserve_seat -> with_args(1)).await.is_err());
+    assert_eq!(CircuitState::Open, breaker.state());
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_with_retry() {
+    let breaker = CircuitBreaker::configure("prices", breaker_config(60_000)).unwrap();
+    let pipeline = compose_shared!(add_10 -> fetch_price);
+    assert!(pipeline(1).await.is_err());
+    assert!(pipeline(1).await.is_err());
+    assert_eq!(6, PRICE_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst));
+    assert_eq!(CircuitState::Open, breaker.state());
+
+    //the open breaker short-circuits the retry loop
+    PRICE_SERVICE_UP.store(true, std::sync::atomic::Ordering::SeqCst);
+    let error = pipeline(1).await.unwrap_err();
+    assert_eq!(Some("circuit_open".to_owned()), error.error_code);
+    assert_eq!(6, PRICE_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst));
+
+    breaker.reset();
+    assert_eq!(120, pipeline(2).await.unwrap());
+}
+
+#[composeable()]
+pub fn sum_pair(pair: (i32, i32)) -> Result<i32, FnError<String>> {
+    Ok(pair.0 + pair.1)
+}
+
+#[composeable()]
+pub fn fail_async(_a: i32) -> BoxFuture<'static, Result<i32, FnError<String>>> {
+    async move { Err(FnError::from("join failure".to_owned())) }.boxed()
+}
+
+#[composeable()]
+pub async fn never_completes(_a: i32) -> Result<i32, FnError<String>> {
+    futures::future::pending().await
+}
+
+#[tokio::test]
+async fn test_compose_join() {
+    let result = compose!(add_10 -> join(add_100, add_100_async) -> sum_pair -> with_args(10)).await;
+    assert_eq!(240, result.unwrap());
+
+    let result = compose!(join(add_10, add_1000_native_async) -> sum_pair -> add_100 -> with_args(1)).await;
+    assert_eq!(1112, result.unwrap());
+
+    let result = compose!(add_10 -> join(never_completes, fail_async) -> sum_pair -> with_args(10)).await;
+    assert_eq!(Some("join failure".to_owned()), result.unwrap_err().description);
+
+    let pipeline = compose_shared!(add_10 -> join(add_100, add_100_async) -> sum_pair);
+    assert_eq!(240, pipeline(10).await.unwrap());
+    assert_eq!(260, pipeline(20).await.unwrap());
+
+    let result = compose_static!(add_10 -> join(add_100, add_100_async, add_10) -> with_args(10)).await;
+    assert_eq!((120, 120, 30), result.unwrap());
+}
+
+#[tokio::test]
+async fn test_compose_branch() {
+    let result = compose!(add_10 -> branch(|a: &i32| *a > 15, add_100, add_10) -> with_args(10));
+    assert_eq!(120, result.unwrap());
+    let result = compose!(add_10 -> branch(|a: &i32| *a > 15, add_100, add_10) -> with_args(1));
+    assert_eq!(21, result.unwrap());
+
+    let result = compose!(add_10 -> branch(|a| *a > 15, add_100_async, add_10) -> add_100 -> with_args(1)).await;
+    assert_eq!(121, result.unwrap());
+    let result = compose!(branch(|a| *a > 15, add_100_async, add_10) -> add_100 -> with_args(20)).await;
+    assert_eq!(220, result.unwrap());
+
+    let pipeline = compose_shared!(add_10 -> branch(|a| *a > 15, add_100, add_1000_native_async));
+    assert_eq!(120, pipeline(10).await.unwrap());
+    assert_eq!(1011, pipeline(1).await.unwrap());
+
+    let result = compose_static!(add_10 -> branch(|a: &i32| *a > 15, add_100, add_1000_native_async) -> with_args(1)).await;
+    assert_eq!(1011, result.unwrap());
+}
+
+#[composeable()]
+pub fn fail_if_odd(a: i32) -> Result<i32, FnError<String>> {
+    if a % 2 == 1 {
+        return Err(FnError::from(format!("{} is odd", a)));
+    }
+    Ok(a)
+}
+
+#[composeable()]
+pub fn error_len(a: i32) -> Result<i32, usize> {
+    Err(a as usize)
+}
+
+#[tokio::test]
+async fn test_compose_error_recovery() {
+    let result = compose!(add_10 -> fail_if_odd -> recover(|_| 0) -> add_100 -> with_args(1));
+    assert_eq!(100, result.unwrap());
+    let result = compose!(add_10 -> fail_if_odd -> recover(|_| 0) -> add_100 -> with_args(2));
+    assert_eq!(112, result.unwrap());
+    let result = compose!(fail_async -> recover(|e| e.description.unwrap().len() as i32) -> with_args(1)).await;
+    assert_eq!(12, result.unwrap());
+
+    let result = compose!(fail_if_odd -> map_err(|e| e.description.unwrap()) -> with_args(1));
+    assert_eq!("1 is odd", result.unwrap_err());
+    let result = compose!(error_len -> map_err(|e| FnError::from(e.to_string())) -> add_100 -> with_args(5));
+    assert_eq!(Some("5".to_owned()), result.unwrap_err().description);
+
+    let result = compose!(add_10 -> fail_if_odd -> or_else(add_100) -> with_args(1));
+    assert_eq!(111, result.unwrap());
+    let result = compose!(fail_if_odd -> or_else(add_100_async) -> add_10 -> with_args(1)).await;
+    assert_eq!(111, result.unwrap());
+    let result = compose!(fail_async -> or_else(fail_if_odd) -> with_args(1)).await;
+    assert_eq!(Some("1 is odd".to_owned()), result.unwrap_err().description);
+
+    let pipeline = compose_shared!(fail_if_odd -> or_else(add_100_async) -> recover(|_| 0) -> map_err(|e| e.description));
+    assert_eq!(101, pipeline(1).await.unwrap());
+    assert_eq!(2, pipeline(2).await.unwrap());
+    let pipeline = compose_shared!(add_10 -> fail_if_odd -> or_else(add_100_async));
+    assert_eq!(111, pipeline(1).await.unwrap());
+
+    let result = compose_static!(fail_if_odd -> or_else(add_1000_native_async) -> map_err(|e: FnError<String>| e.description) -> with_args(1)).await;
+    assert_eq!(1001, result.unwrap());
+    let result = compose_static!(fail_async -> recover(|_| 7) -> add_10 -> with_args(1)).await;
+    assert_eq!(17, result.unwrap());
+}
+
+#[composeable()]
+pub fn multiply_add(factor: i32, a: i32, offset: i32) -> Result<i32, FnError<String>> {
+    Ok(a * factor + offset)
+}
+
+#[composeable()]
+pub async fn prefix_len(prefix: &str, a: i32) -> Result<i32, FnError<String>> {
+    Ok(prefix.len() as i32 + a)
+}
+
+#[tokio::test]
+async fn test_compose_provide_at() {
+    let result = compose!(add_10 -> multiply_add.provide_first(2).provide(5) -> with_args(1));
+    assert_eq!(27, result.unwrap());
+    let result = compose!(multiply_add.provide_at::<2>(5).provide_at::<0>(3) -> add_10 -> with_args(1));
+    assert_eq!(18, result.unwrap());
+    let result = compose!(add_10 -> prefix_len.provide_first("abc") -> with_args(1)).await;
+    assert_eq!(14, result.unwrap());
+
+    let pipeline = compose_shared!(multiply_add.provide_first(2).provide(5) -> prefix_len.provide_at::<0>("abc"));
+    assert_eq!(10, pipeline(1).await.unwrap());
+    assert_eq!(12, pipeline(2).await.unwrap());
+
+    let result = compose_static!(add_10 -> multiply_add.provide_at::<2>(5).provide_first(2) -> prefix_len.provide_first("abc") -> with_args(1)).await;
+    assert_eq!(30, result.unwrap());
+}
+
+impl From<ContainerError> for FnError<String> {
+    fn from(value: ContainerError) -> Self {
+        FnError::from(value.to_string())
+    }
+}
+
+#[derive(Clone)]
+pub struct Settings {
+    pub increment: i32,
+}
+
+pub struct Connection {
+    pub queries: Vec<i32>,
+}
+
+#[composeable(inject(settings))]
+pub fn add_setting(a: i32, settings: Settings) -> Result<i32, FnError<String>> {
+    Ok(a + settings.increment)
+}
+
+#[composeable(inject(conn, settings))]
+pub fn query_sync(conn: &mut Connection, a: i32, settings: &Settings) -> Result<i32, FnError<String>> {
+    conn.queries.push(a);
+    Ok(a * settings.increment + conn.queries.len() as i32)
+}
+
+#[composeable(inject(conn))]
+pub async fn query_async(a: i32, conn: &mut Connection) -> Result<i32, FnError<String>> {
+    conn.queries.push(a);
+    Ok(a + conn.queries.len() as i32)
+}
+
+#[composeable(inject(settings))]
+pub fn add_setting_boxed_async(a: i32, settings: &Settings) -> BoxFuture<'_, Result<i32, FnError<String>>> {
+    async move { Ok(a + settings.increment) }.boxed()
+}
+
+#[tokio::test]
+async fn test_compose_with_container() {
+    let mut container = Container::new();
+    container
+        .register_singleton(Settings { increment: 10 })
+        .register_factory(|| Connection { queries: vec![1] });
+
+    let result = compose!(add_setting -> add_100 -> with_container(&container) -> with_args(1));
+    assert_eq!(111, result.unwrap());
+    let result = compose!(add_10 -> query_sync -> add_setting -> with_container(&container) -> with_args(1));
+    assert_eq!(122, result.unwrap());
+
+    let mut async_container = container.clone();
+    async_container.register_async_factory(|| async { Connection { queries: vec![] } });
+    let result = compose!(add_setting_boxed_async -> query_async -> with_container(&async_container) -> with_args(1)).await;
+    assert_eq!(12, result.unwrap());
+    let result = compose!(add_10 -> query_sync -> with_container(&async_container) -> with_args(1));
+    assert_eq!(
+        Some(ContainerError::AsyncProvider(std::any::type_name::<Connection>()).to_string()),
+        result.unwrap_err().description
+    );
+
+    let pipeline = compose_shared!(query_async -> add_setting -> with_container(&async_container));
+    assert_eq!(12, pipeline(1).await.unwrap());
+    assert_eq!(13, pipeline(2).await.unwrap());
+
+    let result = compose_static!(add_setting -> query_async -> with_container(&container) -> with_args(1)).await;
+    assert_eq!(13, result.unwrap());
+
+    let result = compose!(add_setting -> with_container(&Container::new()) -> with_args(1));
+    assert_eq!(
+        Some(ContainerError::NotRegistered(std::any::type_name::<Settings>()).to_string()),
+        result.unwrap_err().description
+    );
+}
+
+#[tokio::test]
+async fn test_compose_without_container() {
+    let result = compose!(add_setting -> add_100 -> with_args(1));
+    assert_eq!(Some(ContainerError::NoContainer.to_string()), result.unwrap_err().description);
+    let pipeline = compose_shared!(add_10 -> query_async);
+    assert_eq!(Some(ContainerError::NoContainer.to_string()), pipeline(1).await.unwrap_err().description);
+
+    //the previous container is restored when the scope panics
+    let container = Container::new();
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| container.scope(|| panic!("composing failed"))));
+    assert!(panicked.is_err());
+    assert_eq!(Some(ContainerError::NoContainer), Container::current().err());
+}
+
+static PROVIDER_CALLS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+fn counting_provider() -> Result<i32, String> {
+    PROVIDER_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    Ok(5)
+}
+
+#[tokio::test]
+async fn test_compose_lazy_provider() {
+    let result = compose!(fail_if_odd -> multiply_add.provide_first(2).provide_with(counting_provider) -> with_args(1));
+    assert_eq!(Some("1 is odd".to_owned()), result.unwrap_err().description);
+    assert_eq!(0, PROVIDER_CALLS.load(std::sync::atomic::Ordering::SeqCst));
+    let result = compose!(fail_if_odd -> multiply_add.provide_first(2).provide_with(counting_provider) -> with_args(2));
+    assert_eq!(9, result.unwrap());
+    assert_eq!(1, PROVIDER_CALLS.load(std::sync::atomic::Ordering::SeqCst));
+
+    let result = compose!(add_10 -> multiply_add.provide_first(2).provide_with(|| Err("no connection".to_owned())) -> with_args(1));
+    assert_eq!(Some("no connection".to_owned()), result.unwrap_err().description);
+
+    let result = compose!(add_10 -> multiply_add.provide_first(2).provide_async(|| async { Ok::<_, String>(5) }) -> with_args(1)).await;
+    assert_eq!(27, result.unwrap());
+    let result = compose!(prefix_len.provide_async(|| async { Ok::<_, String>(1) }) -> add_10 -> with_args("abc")).await;
+    assert_eq!(14, result.unwrap());
+
+    let pipeline = compose_shared!(multiply_add.provide_first(2).provide_async(|| async { Ok::<_, String>(5) }) -> add_10);
+    assert_eq!(17, pipeline(1).await.unwrap());
+    assert_eq!(19, pipeline(2).await.unwrap());
+
+    let result = compose_static!(add_10 -> multiply_add.provide_with(|| Ok::<_, String>(5)).provide_first(2) -> with_args(1)).await;
+    assert_eq!(27, result.unwrap());
+    let result = compose_static!(multiply_add.provide_async(|| async { Err::<i32, _>("no offset".to_owned()) }).provide_first(2) -> with_args(1)).await;
+    assert_eq!(Some("no offset".to_owned()), result.unwrap_err().description);
+}
+
+#[composeable()]
+pub fn split_pair(a: i32) -> Result<(i32, String), FnError<String>> {
+    Ok((a, a.to_string()))
+}
+
+#[composeable()]
+pub fn join_pair(a: i32, b: String, separator: &str) -> Result<String, FnError<String>> {
+    Ok(format!("{}{}{}", a, separator, b))
+}
+
+#[composeable()]
+pub async fn pair_len(a: i32, b: String) -> Result<i32, FnError<String>> {
+    Ok(a + b.len() as i32)
+}
+
+#[composeable()]
+#[allow(clippy::too_many_arguments)]
+pub fn sum_16(
+    a1: i32, a2: i32, a3: i32, a4: i32, a5: i32, a6: i32, a7: i32, a8: i32,
+    a9: i32, a10: i32, a11: i32, a12: i32, a13: i32, a14: i32, a15: i32, a16: i32,
+) -> Result<i32, FnError<String>> {
+    Ok(a1 + a2 + a3 + a4 + a5 + a6 + a7 + a8 + a9 + a10 + a11 + a12 + a13 + a14 + a15 + a16)
+}
+
+#[tokio::test]
+async fn test_compose_spread() {
+    let result = compose!(add_10 -> split_pair ->> join_pair.provide("-") -> with_args(1));
+    assert_eq!("11-11", result.unwrap());
+    let result = compose!(split_pair ->> pair_len -> add_10 -> with_args(100)).await;
+    assert_eq!(113, result.unwrap());
+
+    let pipeline = compose_shared!(split_pair ->> join_pair.provide(":"));
+    assert_eq!("5:5", pipeline(5).unwrap());
+
+    let result = compose_static!(add_10 -> split_pair ->> pair_len -> with_args(1)).await;
+    assert_eq!(13, result.unwrap());
+}
+
+#[tokio::test]
+async fn test_compose_16_arguments() {
+    let result = compose!(add_10 -> sum_16.provide(1).provide(1).provide(1).provide(1).provide(1).provide(1).provide(1)
+        .provide(1).provide(1).provide(1).provide(1).provide(1).provide(1).provide(1).provide_first(100) -> with_args(1));
+    assert_eq!(125, result.unwrap());
+
+    let result = compose_static!(sum_16.provide(1).provide(1).provide(1).provide(1).provide(1).provide(1).provide(1)
+        .provide(1).provide(1).provide(1).provide(1).provide(1).provide(1).provide(1).provide_at::<1>(100) -> with_args(1)).await;
+    assert_eq!(115, result.unwrap());
+}
+
+#[tokio::test]
+async fn test_compose_multi_arg_head() {
+    let result = compose!(multiply_add -> add_10 -> with_args(2, 3, 4));
+    assert_eq!(20, result.unwrap());
+
+    let result = compose!(add_3_arg_async -> add_10 -> add_100_async -> with_args(1, 2, 3)).await;
+    assert_eq!(116, result.unwrap());
+
+    let result = compose!(multiply_add.provide(4) -> add_10 -> with_args(2, 3));
+    assert_eq!(20, result.unwrap());
+
+    let multiply_add_10 = compose!(multiply_add -> add_10);
+    assert_eq!(20, multiply_add_10(2, 3, 4).unwrap());
+
+    let add_pipeline = compose!(add_async -> add_10 -> add_100);
+    assert_eq!(113, add_pipeline(1, 2).await.unwrap());
+
+    let shared_pipeline = compose_shared!(multiply_add -> add_10);
+    assert_eq!(20, shared_pipeline(2, 3, 4).unwrap());
+    assert_eq!(15, shared_pipeline.clone()(1, 2, 3).unwrap());
+    assert_eq!(20, compose_shared!(multiply_add -> add_10 -> with_args(2, 3, 4)).unwrap());
+
+    let result = compose_static!(multiply_add -> add_async.provide(100) -> with_args(2, 3, 4)).await;
+    assert_eq!(110, result.unwrap());
+}
+
+pipeline! {
+    pub fn add_110(a: i32) -> Result<i32, FnError<String>> {
+        add_10 -> add_100
+    }
+}
+
+pipeline! {
+    /// multiplies and adds 110 to the result
+    pub async fn multiply_add_110(factor: i32, a: i32) -> Result<i32, FnError<String>> {
+        multiply_async -> add_10 -> add_100_async
+    }
+}
+
+pipeline! {
+    pub fn split_and_join(a: i32) -> Result<String, FnError<String>> {
+        split_pair ->> join_pair.provide(":")
+    }
+}
+
+pipeline! {
+    #[composeable]
+    pub fn add_220(a: i32) -> Result<i32, FnError<String>> {
+        add_110 -> add_110
+    }
+}
+
+#[tokio::test]
+async fn test_compose_pipeline() {
+    assert_eq!(111, add_110(1).unwrap());
+    assert_eq!(116, multiply_add_110(2, 3).await.unwrap());
+
+    let result = compose!(add_10 -> add_110 -> add_100 -> with_args(1));
+    assert_eq!(221, result.unwrap());
+    let result = compose!(add_110 -> multiply_add_110.provide(2) -> with_args(1)).await;
+    assert_eq!(332, result.unwrap());
+    let result = compose!(add_10 -> split_and_join -> with_args(1));
+    assert_eq!("11:11", result.unwrap());
+
+    let shared_pipeline = compose_shared!(add_110 -> add_110);
+    assert_eq!(221, shared_pipeline(1).unwrap());
+    let result = compose!(add_220 -> add_10 -> with_args(1));
+    assert_eq!(231, result.unwrap());
+    let result = compose_static!(add_110 -> multiply_add_110.provide(2) -> with_args(1)).await;
+    assert_eq!(332, result.unwrap());
+}
+
+pub mod checked {
+    use super::*;
+
+    #[composeable()]
+    pub fn add_10(a: i32) -> Result<i32, FnError<String>> {
+        a.checked_add(10).ok_or_else(|| FnError::from("overflow".to_owned()))
+    }
+
+    pub mod pairs {
+        use super::*;
+
+        #[composeable()]
+        pub async fn split(a: i32) -> Result<(i32, i32), FnError<String>> {
+            Ok((a / 2, a - a / 2))
+        }
+
+        #[composeable()]
+        pub fn add(a: i32, b: i32) -> Result<i32, FnError<String>> {
+            Ok(a + b)
+        }
+    }
+}
+
+pub mod aliased {
+    use super::*;
+
+    //an alias of Result is accepted whatever its name
+    type Option = Result<i32, FnError<String>>;
+
+    #[composeable()]
+    pub fn sub_10(a: i32) -> Option {
+        a.checked_sub(10).ok_or_else(|| FnError::from("overflow".to_owned()))
+    }
+}
+
+#[tokio::test]
+async fn test_compose_function_paths() {
+    let result = compose!(checked::add_10 -> add_10 -> with_args(1));
+    assert_eq!(21, result.unwrap());
+    assert!(compose!(add_10 -> checked::add_10 -> with_args(i32::MAX - 15)).is_err());
+
+    let result = compose!(crate::checked::pairs::split ->> checked::pairs::add -> checked::add_10 -> with_args(5)).await;
+    assert_eq!(15, result.unwrap());
+    let result = compose!(checked::add_10 -> checked::pairs::add.provide_with(|| Ok::<_, String>(2)) -> with_args(1));
+    assert_eq!(13, result.unwrap());
+    let result = compose!(checked::pairs::add.provide(2) -> join(checked::add_10, add_100) -> sum_pair -> with_args(1)).await;
+    assert_eq!(116, result.unwrap());
+    let result = compose!(add_10 -> branch(|a| *a > 15, checked::add_10, add_100) -> or_else(checked::add_10) -> with_args(10));
+    assert_eq!(30, result.unwrap());
+
+    let shared_pipeline = compose_shared!(checked::pairs::split ->> checked::pairs::add -> join(checked::add_10, add_10) -> sum_pair);
+    assert_eq!(40, shared_pipeline(10).await.unwrap());
+
+    let result = compose_static!(checked::pairs::split ->> checked::pairs::add -> branch(|a: &i32| *a > 15, checked::add_10, add_100) -> with_args(5)).await;
+    assert_eq!(105, result.unwrap());
+    let result = compose!(checked::add_10 -> aliased::sub_10 -> with_args(1));
+    assert_eq!(1, result.unwrap());
+}
+
+#[tokio::test]
+async fn test_compose_closures() {
+    let result = compose!(add_10 -> |a| Ok(a * 2) -> add_100 -> with_args(1));
+    assert_eq!(122, result.unwrap());
+    let result = compose!(split_pair -> |pair| Ok(pair.0 + pair.1.len() as i32) -> with_args(100));
+    assert_eq!(103, result.unwrap());
+    let result = compose!(add_10 -> |a: i32| -> Result<String, FnError<String>> { Ok(a.to_string()) } -> with_args(1));
+    assert_eq!("11", result.unwrap());
+    let result = compose!(add_10 -> |a| if a > 20 { Ok(a) } else { Err(FnError::from("too small".to_owned())) } -> add_100 -> with_args(1));
+    assert!(result.is_err());
+
+    let offset = 5;
+    let result = compose!(add_10 -> move |a| async move { Ok(a + offset) } -> add_100 -> with_args(1)).await;
+    assert_eq!(116, result.unwrap());
+    let result = compose!(add_100_async -> async |a| Ok(a - 1) -> add_10 -> with_args(1)).await;
+    assert_eq!(110, result.unwrap());
+    let result = compose!(multiply_add -> |a| Ok(a * 2) -> with_args(2, 3, 4));
+    assert_eq!(20, result.unwrap());
+    let result = compose!(checked::add_10 -> |a| Ok(a + 1) -> join(add_10, add_100) -> |pair| Ok(pair.0 * pair.1) -> with_args(1)).await;
+    assert_eq!(22 * 112, result.unwrap());
+
+    let shared_pipeline = compose_shared!(add_10 -> |a| Ok(a * 2) -> |a| async move { Ok(a + 1) });
+    assert_eq!(23, shared_pipeline(1).await.unwrap());
+    assert_eq!(25, shared_pipeline.clone()(2).await.unwrap());
+
+    let result = compose_static!(add_10 -> |a| Ok(a * 2) -> async move |a| Ok(a + offset) -> with_args(1)).await;
+    assert_eq!(27, result.unwrap());
+}
+
+pub struct Account {
+    balance: i32,
+}
+
+impl Account {
+    #[composeable()]
+    pub fn deposit(&mut self, amount: i32) -> Result<i32, FnError<String>> {
+        self.balance += amount;
+        Ok(self.balance)
+    }
+
+    #[composeable()]
+    pub fn balance_with(&self, amount: i32) -> Result<i32, FnError<String>> {
+        Ok(self.balance + amount)
+    }
+
+    #[composeable(retry = Fixed::from_millis(10).take(2))]
+    pub async fn withdraw(&mut self, amount: i32) -> Result<i32, FnError<String>> {
+        if amount > self.balance {
+            return Err(FnError::from("insufficient balance".to_owned()));
+        }
+        self.balance -= amount;
+        Ok(self.balance)
+    }
+}
+
+pub trait Ledger {
+    #[composeable()]
+    fn record(&mut self, amount: i32) -> BoxFuture<'_, Result<i32, FnError<String>>>;
+}
+
+impl Ledger for Account {
+    fn record(&mut self, amount: i32) -> BoxFuture<'_, Result<i32, FnError<String>>> {
+        async move {
+            self.balance += amount;
+            Ok(self.balance)
+        }
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn test_compose_methods() {
+    let mut account = Account { balance: 100 };
+    let result = compose!(account.deposit -> add_10 -> with_args(5));
+    assert_eq!(115, result.unwrap());
+    let result = compose!(add_10 -> account.withdraw -> add_100 -> with_args(5)).await;
+    assert_eq!(190, result.unwrap());
+    assert!(compose!(account.withdraw -> with_args(1000)).await.is_err());
+    let result = compose!(account.balance_with -> add_10 -> with_args(1));
+    assert_eq!(101, result.unwrap());
+    let result = compose!(account.record -> add_10 -> with_args(10)).await;
+    assert_eq!(110, result.unwrap());
+    assert_eq!(100, account.balance);
+
+    let result = compose!(<Account>::deposit.provide_first(&mut account) -> add_10 -> with_args(10));
+    assert_eq!(120, result.unwrap());
+    let result = compose!(add_10 -> <Account>::balance_with.provide_first(&account) -> with_args(0));
+    assert_eq!(120, result.unwrap());
+
+    let result = compose_static!(add_10 -> account.withdraw -> add_100 -> with_args(10)).await;
+    assert_eq!(190, result.unwrap());
+    let result = compose_static!(account.balance_with -> add_10 -> with_args(1)).await;
+    assert_eq!(101, result.unwrap());
+
+    let shared_pipeline = compose_shared!(<Account>::balance_with.provide_first(&account) -> add_10);
+    assert_eq!(101, shared_pipeline(1).unwrap());
+
+    let mut ledger = Account { balance: 0 };
+    let result = compose!(<Account as Ledger>::record.provide_first(&mut ledger) -> add_10 -> with_args(10)).await;
+    assert_eq!(20, result.unwrap());
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Dto(String);
+
+impl From<i32> for Dto {
+    fn from(value: i32) -> Self {
+        Dto(value.to_string())
+    }
+}
+
+impl From<String> for Dto {
+    fn from(value: String) -> Self {
+        Dto(value)
+    }
+}
+
+#[composeable()]
+pub fn to_dto<T: Into<Dto>>(t: T) -> Result<Dto, FnError<String>> {
+    Ok(t.into())
+}
+
+#[composeable()]
+pub fn parse_number<T>(text: String) -> Result<T, FnError<String>>
+where
+    T: std::str::FromStr,
+{
+    text.parse().map_err(|_| FnError::from(format!("{text} is not a number")))
+}
+
+#[composeable()]
+pub async fn describe<T: std::fmt::Debug + Send>(t: T, label: &str) -> Result<String, FnError<String>> {
+    Ok(format!("{label}: {t:?}"))
+}
+
+#[composeable(retry = Fixed::from_millis(10).take(1))]
+pub fn checked_dto<T>(t: T) -> Result<Dto, FnError<String>>
+where
+    T: Into<Dto> + Copy,
+{
+    Ok(t.into())
+}
+
+#[composeable(inject(settings))]
+pub fn scale<T: Into<i32>>(t: T, settings: &Settings) -> Result<i32, FnError<String>> {
+    Ok(t.into() * settings.increment)
+}
+
+pipeline! {
+    pub fn number_to_dto<T>(text: String) -> Result<Dto, FnError<String>>
+    where
+        T: std::str::FromStr + Into<Dto> + Send,
+    {
+        parse_number::<T> -> to_dto::<T>
+    }
+}
+
+#[tokio::test]
+async fn test_compose_generic_functions() {
+    let result = compose!(add_10 -> to_dto -> with_args(1));
+    assert_eq!(Dto("11".to_owned()), result.unwrap());
+    let result = compose!(parse_number::<i32> -> add_10 -> to_dto::<i32> -> with_args("5".to_owned()));
+    assert_eq!(Dto("15".to_owned()), result.unwrap());
+    assert!(compose!(parse_number::<u8> -> with_args("300".to_owned())).is_err());
+    let result = compose!(parse_number::<i64> -> describe::<i64>.provide("number") -> with_args("7".to_owned())).await;
+    assert_eq!("number: 7", result.unwrap());
+    let result = compose!(crate::parse_number::<i32> -> join(add_10, to_dto::<i32>) -> with_args("1".to_owned())).await;
+    assert_eq!((11, Dto("1".to_owned())), result.unwrap());
+    let result = compose!(add_10 -> branch(|a| *a > 5, to_dto::<i32>, to_dto) -> with_args(1));
+    assert_eq!(Dto("11".to_owned()), result.unwrap());
+
+    let shared_pipeline = compose_shared!(parse_number::<i32> -> add_10 -> to_dto::<i32>);
+    assert_eq!(Dto("12".to_owned()), shared_pipeline("2".to_owned()).unwrap());
+
+    let result = compose_static!(parse_number::<i32> -> add_10 -> to_dto::<i32> -> with_args("3".to_owned())).await;
+    assert_eq!(Dto("13".to_owned()), result.unwrap());
+
+    let result = compose!(add_10 -> checked_dto::<i32> -> with_args(1));
+    assert_eq!(Dto("11".to_owned()), result.unwrap());
+    let result = compose!(number_to_dto::<i32> -> with_args("4".to_owned()));
+    assert_eq!(Dto("4".to_owned()), result.unwrap());
+
+    let mut container = Container::new();
+    container.register_singleton(Settings { increment: 10 });
+    let result = compose!(scale::<i8> -> add_10 -> with_container(&container) -> with_args(2));
+    assert_eq!(30, result.unwrap());
+}
+
+mod conversions {
+    use function_compose::{compose, compose_shared, compose_static, composeable};
+    use futures::{future::BoxFuture, FutureExt};
+
+    struct Celsius(f64);
+
+    #[composeable()]
+    fn parse_celsius(input: String) -> Result<Celsius, String> {
+        input.parse().map(Celsius).map_err(|_| format!("invalid temperature {input}"))
+    }
+
+    #[composeable()]
+    fn to_fahrenheit(celsius: Celsius) -> BoxFuture<'static, Result<f64, String>> {
+        async move { Ok(celsius.0 * 9.0 / 5.0 + 32.0) }.boxed()
+    }
+
+    pub(crate) async fn fahrenheit(input: String) -> Result<f64, String> {
+        compose!(parse_celsius -> to_fahrenheit -> with_args(input)).await
+    }
+
+    pub(crate) async fn fahrenheit_shared(input: String) -> Result<f64, String> {
+        compose_shared!(parse_celsius -> to_fahrenheit)(input).await
+    }
+
+    pub(crate) async fn fahrenheit_static(input: String) -> Result<f64, String> {
+        compose_static!(parse_celsius -> to_fahrenheit -> with_args(input)).await
+    }
+
+    mod nested {
+        #[tokio::test]
+        async fn test_compose_private_function_of_parent_module() {
+            use function_compose::compose;
+            let result = compose!(super::parse_celsius -> super::to_fahrenheit -> with_args("-40".to_owned())).await;
+            assert_eq!(-40.0, result.unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_compose_private_functions() {
+    assert_eq!(212.0, conversions::fahrenheit("100".to_owned()).await.unwrap());
+    assert_eq!(32.0, conversions::fahrenheit_shared("0".to_owned()).await.unwrap());
+    assert_eq!(50.0, conversions::fahrenheit_static("10".to_owned()).await.unwrap());
+    assert!(conversions::fahrenheit("warm".to_owned()).await.is_err());
+}
+
+#[test]
+fn test_retry_delay_strategies() {
+    let millis = |delays: Vec<std::time::Duration>| delays.iter().map(|delay| delay.as_millis()).collect::<Vec<_>>();
+    assert_eq!(vec![10, 10, 10], millis(Fixed::from_millis(10).take(3).collect()));
+    assert_eq!(vec![10, 100, 1000, 10000], millis(Exponential::from_millis(10).take(4).collect()));
+    assert_eq!(vec![10, 20, 40, 80], millis(Exponential::from(std::time::Duration::from_millis(10)).take(4).collect()));
+    assert_eq!(vec![10, 30, 90], millis(Exponential::from_millis(10).factor(3.0).take(3).collect()));
+    assert_eq!(vec![10, 10, 20, 30, 50], millis(Fibonacci::from_millis(10).take(5).collect()));
+    assert!(Fixed::from_millis(10).map(jitter).take(20).all(|delay| delay <= std::time::Duration::from_millis(10)));
+    let limited = Fixed::from_millis(40).max_elapsed(std::time::Duration::from_millis(100));
+    assert_eq!(vec![40, 40], millis(limited.collect()));
+}